@@ -0,0 +1,568 @@
+// Instruction builders for the escrow program. Every account, including the token program
+// behind each mint and the configured treasury, is resolved through an `AccountFetcher`,
+// an `RpcClient` with the default `rpc` feature.
+//
+// Mints with an active transfer hook get the hook's extra accounts appended for every
+// transfer the instruction makes, read from the hook's validation account.
+
+use std::{collections::HashMap, fmt};
+
+use anchor_escrow::state::{Config, Escrow, LockKind};
+use anchor_lang::{prelude::Pubkey, solana_program::instruction::Instruction, system_program, AccountDeserialize, InstructionData, ToAccountMetas};
+use anchor_spl::{
+    associated_token::{get_associated_token_address_with_program_id, spl_associated_token_account},
+    token::spl_token,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{transfer_hook::TransferHook, BaseStateWithExtensions, StateWithExtensions},
+    },
+};
+use spl_transfer_hook_interface::offchain::{add_extra_account_metas_for_execute, AccountDataResult};
+
+pub use anchor_escrow::ID as PROGRAM_ID;
+
+#[derive(Clone, Debug)]
+pub struct FetchedAccount {
+    pub owner: Pubkey,
+    pub data: Vec<u8>,
+}
+
+pub trait AccountFetcher {
+    fn fetch_account(&self, address: &Pubkey) -> Result<FetchedAccount, ClientError>;
+}
+
+#[derive(Debug)]
+pub enum ClientError {
+    AccountNotFound(Pubkey),
+    InvalidAccount(Pubkey), // Not a mint, escrow or config account where one was expected
+    TransferHook(Pubkey, String), // The hook's extra accounts for this mint could not be resolved
+    Rpc(String),
+}
+
+impl fmt::Display for ClientError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientError::AccountNotFound(address) => write!(f, "account {address} not found"),
+            ClientError::InvalidAccount(address) => write!(f, "account {address} is not the expected escrow account type"),
+            ClientError::TransferHook(address, err) => write!(f, "transfer hook accounts for mint {address}: {err}"),
+            ClientError::Rpc(err) => write!(f, "rpc error: {err}"),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+// In-memory accounts, for tests and callers that already hold the data
+impl AccountFetcher for HashMap<Pubkey, FetchedAccount> {
+    fn fetch_account(&self, address: &Pubkey) -> Result<FetchedAccount, ClientError> {
+        self.get(address).cloned().ok_or(ClientError::AccountNotFound(*address))
+    }
+}
+
+#[cfg(feature = "rpc")]
+impl AccountFetcher for solana_rpc_client::rpc_client::RpcClient {
+    fn fetch_account(&self, address: &Pubkey) -> Result<FetchedAccount, ClientError> {
+        let account = self
+            .get_account(&solana_address::Address::new_from_array(address.to_bytes()))
+            .map_err(|err| ClientError::Rpc(err.to_string()))?;
+
+        Ok(FetchedAccount { owner: Pubkey::new_from_array(account.owner.to_bytes()), data: account.data })
+    }
+}
+
+// Timing terms of a new escrow, in slots or seconds
+#[derive(Clone, Copy, Debug)]
+pub struct Lock {
+    pub kind: LockKind,
+    pub lock_period: i64, // Before the escrow can be taken
+    pub commit_period: i64, // Before the maker can refund
+}
+
+impl Lock {
+    pub fn slots(lock_period: i64, commit_period: i64) -> Self {
+        Self { kind: LockKind::Slot, lock_period, commit_period }
+    }
+
+    pub fn seconds(lock_period: i64, commit_period: i64) -> Self {
+        Self { kind: LockKind::UnixTimestamp, lock_period, commit_period }
+    }
+}
+
+impl Default for Lock {
+    fn default() -> Self {
+        Self::slots(0, 0)
+    }
+}
+
+pub fn find_config_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &PROGRAM_ID).0
+}
+
+pub fn find_mint_entry_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"mint", mint.as_ref()], &PROGRAM_ID).0
+}
+
+pub fn find_escrow_address(maker: &Pubkey, seed: u64) -> Pubkey {
+    Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &seed.to_le_bytes()], &PROGRAM_ID).0
+}
+
+// The vault is the escrow's associated token account for mint A
+pub fn find_vault_address(escrow: &Pubkey, mint_a: &Pubkey, token_program_a: &Pubkey) -> Pubkey {
+    get_associated_token_address_with_program_id(escrow, mint_a, token_program_a)
+}
+
+pub struct EscrowClient<F> {
+    fetcher: F,
+}
+
+impl<F: AccountFetcher> EscrowClient<F> {
+    pub fn new(fetcher: F) -> Self {
+        Self { fetcher }
+    }
+
+    // SPL Token or Token-2022, whichever owns the mint. Each program's native mint is its own
+    pub fn token_program(&self, mint: &Pubkey) -> Result<Pubkey, ClientError> {
+        if *mint == spl_token::native_mint::ID {
+            return Ok(spl_token::ID);
+        }
+
+        if *mint == spl_token_2022::native_mint::ID {
+            return Ok(spl_token_2022::ID);
+        }
+
+        let account = self.fetcher.fetch_account(mint)?;
+
+        if account.owner == spl_token::ID {
+            return Ok(spl_token::ID);
+        }
+
+        if account.owner != spl_token_2022::ID {
+            return Err(ClientError::InvalidAccount(*mint));
+        }
+
+        Ok(spl_token_2022::ID)
+    }
+
+    // Program of the mint's transfer hook, none for mints that transfer without one
+    pub fn transfer_hook_program(&self, mint: &Pubkey) -> Result<Option<Pubkey>, ClientError> {
+        if is_native_mint(mint) {
+            return Ok(None);
+        }
+
+        let account = self.fetcher.fetch_account(mint)?;
+
+        if account.owner != spl_token_2022::ID {
+            return Ok(None);
+        }
+
+        let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&account.data).map_err(|_| ClientError::InvalidAccount(*mint))?;
+
+        // A hook extension with no program set transfers like any other mint
+        Ok(state.get_extension::<TransferHook>().ok().and_then(|hook| hook.program_id.into()))
+    }
+
+    pub fn config(&self) -> Result<Config, ClientError> {
+        self.deserialize(&find_config_address())
+    }
+
+    pub fn escrow(&self, maker: &Pubkey, seed: u64) -> Result<Escrow, ClientError> {
+        self.deserialize(&find_escrow_address(maker, seed))
+    }
+
+    // Plain escrow with no expiry, designated taker, NFT terms or auction
+    #[allow(clippy::too_many_arguments)]
+    pub fn make_ix(&self, maker: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, seed: u64, deposit: u64, receive: u64, lock: Lock) -> Result<Instruction, ClientError> {
+        self.make_ix_with(maker, mint_a, mint_b, anchor_escrow::instruction::Make {
+            seed,
+            deposit,
+            receive,
+            lock_kind: lock.kind,
+            lock_period: lock.lock_period,
+            commit_period: lock.commit_period,
+            expires_at: None,
+            taker: None,
+            nft: None,
+            auction: None,
+        })
+    }
+
+    pub fn make_ix_with(&self, maker: &Pubkey, mint_a: &Pubkey, mint_b: &Pubkey, args: anchor_escrow::instruction::Make) -> Result<Instruction, ClientError> {
+        let token_program_a = self.token_program(mint_a)?;
+        let token_program_b = self.token_program(mint_b)?;
+
+        let escrow = find_escrow_address(maker, args.seed);
+
+        let maker_ata_a = token_account(maker, mint_a, &token_program_a);
+        let vault = find_vault_address(&escrow, mint_a, &token_program_a);
+        let deposit = args.deposit;
+
+        let accounts = anchor_escrow::accounts::Make {
+            maker: *maker,
+            mint_a: *mint_a,
+            mint_b: *mint_b,
+            // Native SOL is deposited straight from the maker's lamports
+            maker_ata_a,
+            escrow,
+            config: find_config_address(),
+            mint_a_entry: find_mint_entry_address(mint_a),
+            mint_b_entry: find_mint_entry_address(mint_b),
+            vault,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program_a,
+            token_program_b,
+            system_program: system_program::ID,
+        };
+
+        let mut ix = Instruction { program_id: PROGRAM_ID, accounts: accounts.to_account_metas(None), data: args.data() };
+
+        if let Some(maker_ata_a) = maker_ata_a {
+            self.add_transfer_hook_accounts(&mut ix, mint_a, &maker_ata_a, &vault, maker, deposit)?;
+        }
+
+        Ok(ix)
+    }
+
+    // Collection offers are paid with a member NFT rather than the escrow's mint B,
+    // so they still need their take accounts built by hand.
+    // Priced at the terms fetched now, the take fails if the maker raises them before it lands
+    pub fn take_ix(&self, taker: &Pubkey, maker: &Pubkey, seed: u64) -> Result<Instruction, ClientError> {
+        self.build_take_ix(taker, maker, seed, |escrow| {
+            (escrow.receive, anchor_escrow::instruction::Take { max_amount_b: escrow.receive, referrer_fee_bps: 0 }.data())
+        })
+    }
+
+    pub fn take_partial_ix(&self, taker: &Pubkey, maker: &Pubkey, seed: u64, amount_b: u64) -> Result<Instruction, ClientError> {
+        self.build_take_ix(taker, maker, seed, |escrow| {
+            (amount_b, anchor_escrow::instruction::TakePartial { amount_b, min_amount_a: escrow.share_of_deposit(amount_b), referrer_fee_bps: 0 }.data())
+        })
+    }
+
+    pub fn refund_ix(&self, maker: &Pubkey, seed: u64) -> Result<Instruction, ClientError> {
+        let escrow = self.escrow(maker, seed)?;
+        let token_program = self.token_program(&escrow.mint_a)?;
+
+        let escrow_address = find_escrow_address(maker, seed);
+        let maker_ata_a = token_account(maker, &escrow.mint_a, &token_program);
+        let vault = find_vault_address(&escrow_address, &escrow.mint_a, &token_program);
+
+        let accounts = anchor_escrow::accounts::Refund {
+            maker: *maker,
+            mint_a: escrow.mint_a,
+            // A native SOL vault is closed straight to the maker
+            maker_ata_a,
+            escrow: escrow_address,
+            vault,
+            token_program,
+            system_program: system_program::ID,
+        };
+
+        let mut ix = Instruction { program_id: PROGRAM_ID, accounts: accounts.to_account_metas(None), data: anchor_escrow::instruction::Refund {}.data() };
+
+        if let Some(maker_ata_a) = maker_ata_a {
+            self.add_transfer_hook_accounts(&mut ix, &escrow.mint_a, &vault, &maker_ata_a, &escrow_address, escrow.deposit)?;
+        }
+
+        Ok(ix)
+    }
+
+    // `data` returns the mint B amount being paid alongside the instruction data
+    fn build_take_ix(&self, taker: &Pubkey, maker: &Pubkey, seed: u64, data: impl FnOnce(&Escrow) -> (u64, Vec<u8>)) -> Result<Instruction, ClientError> {
+        let escrow = self.escrow(maker, seed)?;
+        let (amount_b, data) = data(&escrow);
+        let config = self.config()?;
+
+        let token_program_a = self.token_program(&escrow.mint_a)?;
+        let token_program_b = self.token_program(&escrow.mint_b)?;
+
+        let escrow_address = find_escrow_address(maker, seed);
+        let taker_ata_a = get_associated_token_address_with_program_id(taker, &escrow.mint_a, &token_program_a);
+        let taker_ata_b = token_account(taker, &escrow.mint_b, &token_program_b);
+        let maker_ata_b = token_account(maker, &escrow.mint_b, &token_program_b);
+        let treasury_ata_b = if config.fee_bps > 0 { token_account(&config.treasury, &escrow.mint_b, &token_program_b) } else { None };
+        let vault = find_vault_address(&escrow_address, &escrow.mint_a, &token_program_a);
+
+        let accounts = anchor_escrow::accounts::Take {
+            taker: *taker,
+            maker: *maker,
+            mint_a: escrow.mint_a,
+            mint_b: escrow.mint_b,
+            taker_ata_a,
+            // Native SOL is paid from the taker's lamports straight to each wallet
+            taker_ata_b,
+            maker_ata_b,
+            config: find_config_address(),
+            treasury: config.treasury,
+            treasury_ata_b,
+            referrer_ata_b: None,
+            escrow: escrow_address,
+            vault,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program_a,
+            token_program_b,
+            system_program: system_program::ID,
+        };
+
+        let mut ix = Instruction { program_id: PROGRAM_ID, accounts: accounts.to_account_metas(None), data };
+
+        self.add_transfer_hook_accounts(&mut ix, &escrow.mint_a, &vault, &taker_ata_a, &escrow_address, escrow.share_of_deposit(amount_b))?;
+
+        if let (Some(taker_ata_b), Some(maker_ata_b)) = (taker_ata_b, maker_ata_b) {
+            let protocol_fee = config.protocol_fee(amount_b).map_err(|err| ClientError::TransferHook(escrow.mint_b, err.to_string()))?;
+
+            self.add_transfer_hook_accounts(&mut ix, &escrow.mint_b, &taker_ata_b, &maker_ata_b, taker, amount_b - protocol_fee)?;
+
+            if let Some(treasury_ata_b) = treasury_ata_b {
+                self.add_transfer_hook_accounts(&mut ix, &escrow.mint_b, &taker_ata_b, &treasury_ata_b, taker, protocol_fee)?;
+            }
+        }
+
+        Ok(ix)
+    }
+
+    // Append the extra accounts the mint's transfer hook needs for one transfer the program
+    // makes, plus the hook program and its validation account. Unhooked mints need none.
+    fn add_transfer_hook_accounts(&self, ix: &mut Instruction, mint: &Pubkey, source: &Pubkey, destination: &Pubkey, authority: &Pubkey, amount: u64) -> Result<(), ClientError> {
+        let Some(hook_program) = self.transfer_hook_program(mint)? else {
+            return Ok(());
+        };
+
+        // The fetcher is synchronous, so every future is ready by the time it is polled
+        let fetch_account_data = |address: Pubkey| {
+            let result: AccountDataResult = match self.fetcher.fetch_account(&address) {
+                Ok(account) => Ok(Some(account.data)),
+                Err(ClientError::AccountNotFound(_)) => Ok(None),
+                Err(err) => Err(err.into()),
+            };
+
+            std::future::ready(result)
+        };
+
+        futures_executor::block_on(add_extra_account_metas_for_execute(
+            ix,
+            &hook_program,
+            source,
+            mint,
+            destination,
+            authority,
+            amount,
+            fetch_account_data,
+        ))
+        .map_err(|err| ClientError::TransferHook(*mint, err.to_string()))
+    }
+
+    fn deserialize<T: AccountDeserialize>(&self, address: &Pubkey) -> Result<T, ClientError> {
+        let account = self.fetcher.fetch_account(address)?;
+
+        if account.owner != PROGRAM_ID {
+            return Err(ClientError::InvalidAccount(*address));
+        }
+
+        T::try_deserialize(&mut account.data.as_slice()).map_err(|_| ClientError::InvalidAccount(*address))
+    }
+}
+
+// Associated token account of `owner`, none for native SOL which moves as lamports
+fn token_account(owner: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Option<Pubkey> {
+    (!is_native_mint(mint)).then(|| get_associated_token_address_with_program_id(owner, mint, token_program))
+}
+
+// Both token programs' wSOL mints, as the program accepts either
+fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::ID || *mint == spl_token_2022::native_mint::ID
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anchor_escrow::state::NftTerms;
+    use anchor_lang::{solana_program::program_pack::Pack, AccountSerialize, Discriminator};
+    use anchor_spl::token_2022::spl_token_2022::{
+        extension::{BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut},
+        state::Mint,
+    };
+    use spl_tlv_account_resolution::{account::ExtraAccountMeta, state::ExtraAccountMetaList};
+    use spl_transfer_hook_interface::{get_extra_account_metas_address, instruction::ExecuteInstruction};
+
+    fn program_account<T: AccountSerialize>(state: &T) -> FetchedAccount {
+        let mut data = Vec::new();
+        state.try_serialize(&mut data).unwrap();
+
+        FetchedAccount { owner: PROGRAM_ID, data }
+    }
+
+    fn mint_account(token_program: Pubkey) -> FetchedAccount {
+        let mut data = vec![0; Mint::LEN];
+        Mint { decimals: 6, is_initialized: true, ..Mint::default() }.pack_into_slice(&mut data);
+
+        FetchedAccount { owner: token_program, data }
+    }
+
+    fn transfer_hook_mint_account(hook_program: Option<Pubkey>) -> FetchedAccount {
+        let mut data = vec![0; ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferHook]).unwrap()];
+
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        state.init_extension::<TransferHook>(true).unwrap().program_id = hook_program.try_into().unwrap();
+        state.base = Mint { decimals: 6, is_initialized: true, ..Mint::default() };
+        state.pack_base();
+        state.init_account_type().unwrap();
+
+        FetchedAccount { owner: spl_token_2022::ID, data }
+    }
+
+    // Validation account of a hook whose only extra accounts are fixed addresses
+    fn extra_account_metas_account(hook_program: Pubkey, extra_accounts: &[Pubkey]) -> FetchedAccount {
+        let extra_metas: Vec<ExtraAccountMeta> = extra_accounts.iter().map(|address| ExtraAccountMeta::new_with_pubkey(address, false, false).unwrap()).collect();
+
+        let mut data = vec![0; ExtraAccountMetaList::size_of(extra_metas.len()).unwrap()];
+        ExtraAccountMetaList::init::<ExecuteInstruction>(&mut data, &extra_metas).unwrap();
+
+        FetchedAccount { owner: hook_program, data }
+    }
+
+    fn config(treasury: Pubkey, fee_bps: u16) -> Config {
+        Config { admin: treasury, treasury, fee_bps, max_referrer_fee_bps: 100, paused: false, allowlist_only: false, reject_risky_mints: false, bump: 255 }
+    }
+
+    fn escrow(maker: Pubkey, seed: u64, mint_a: Pubkey, mint_b: Pubkey) -> Escrow {
+        Escrow {
+            seed, maker, mint_a, mint_b, deposit: 100, receive: 50, bump: 255, lock_kind: LockKind::Slot, start_time: 0,
+            lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: NftTerms::default(), auction: None,
+        }
+    }
+
+    #[test]
+    fn test_make_ix_resolves_token_programs() {
+        let (maker, mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let accounts = HashMap::from([
+            (mint_a, mint_account(spl_token_2022::ID)),
+            (mint_b, mint_account(spl_token::ID)),
+        ]);
+
+        let ix = EscrowClient::new(accounts).make_ix(&maker, &mint_a, &mint_b, 7, 100, 50, Lock::slots(5, 10)).unwrap();
+
+        let escrow = find_escrow_address(&maker, 7);
+        let vault = find_vault_address(&escrow, &mint_a, &spl_token_2022::ID);
+
+        assert_eq!(ix.program_id, PROGRAM_ID);
+        assert!(ix.data.starts_with(anchor_escrow::instruction::Make::DISCRIMINATOR));
+
+        let keys: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+        assert!(keys.contains(&escrow), "Escrow PDA should be included");
+        assert!(keys.contains(&vault), "Vault should be derived with mint A's Token-2022 program");
+        assert!(keys.contains(&get_associated_token_address_with_program_id(&maker, &mint_a, &spl_token_2022::ID)), "Maker ATA A should use Token-2022");
+        assert!(keys.contains(&spl_token::ID), "Mint B's legacy token program should be included");
+    }
+
+    #[test]
+    fn test_take_ix_reads_escrow_and_config() {
+        let (maker, taker, treasury, mint_a) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let mint_b = spl_token::native_mint::ID;
+
+        let accounts = HashMap::from([
+            (mint_a, mint_account(spl_token::ID)),
+            (find_config_address(), program_account(&config(treasury, 100))),
+            (find_escrow_address(&maker, 9), program_account(&escrow(maker, 9, mint_a, mint_b))),
+        ]);
+
+        let ix = EscrowClient::new(accounts).take_ix(&taker, &maker, 9).unwrap();
+
+        let keys: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+        assert!(keys.contains(&treasury), "Treasury should come from the config");
+        assert!(keys.contains(&get_associated_token_address_with_program_id(&taker, &mint_a, &spl_token::ID)), "Taker ATA A should be included");
+        // Native SOL is paid as lamports, so no mint B token accounts are passed
+        assert!(!keys.contains(&get_associated_token_address_with_program_id(&maker, &mint_b, &spl_token::ID)), "Maker ATA B should be omitted");
+        assert!(!keys.contains(&get_associated_token_address_with_program_id(&treasury, &mint_b, &spl_token::ID)), "Treasury ATA B should be omitted");
+    }
+
+    #[test]
+    fn test_refund_ix_requires_escrow() {
+        let maker = Pubkey::new_unique();
+
+        let result = EscrowClient::new(HashMap::new()).refund_ix(&maker, 1);
+
+        assert!(matches!(result, Err(ClientError::AccountNotFound(address)) if address == find_escrow_address(&maker, 1)));
+    }
+
+    #[test]
+    fn test_token_program_for_native_mints() {
+        let client = EscrowClient::new(HashMap::new());
+
+        assert_eq!(client.token_program(&spl_token::native_mint::ID).unwrap(), spl_token::ID);
+        assert_eq!(client.token_program(&spl_token_2022::native_mint::ID).unwrap(), spl_token_2022::ID);
+        assert!(token_account(&Pubkey::new_unique(), &spl_token_2022::native_mint::ID, &spl_token_2022::ID).is_none(), "Token-2022 wSOL should move as lamports");
+    }
+
+    #[test]
+    fn test_make_ix_resolves_transfer_hook_accounts() {
+        let (maker, hooked_mint, unhooked_mint, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (hook_program, extra_account) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let validation_address = get_extra_account_metas_address(&hooked_mint, &hook_program);
+
+        let accounts = HashMap::from([
+            (hooked_mint, transfer_hook_mint_account(Some(hook_program))),
+            (unhooked_mint, transfer_hook_mint_account(None)),
+            (mint_b, mint_account(spl_token::ID)),
+            (validation_address, extra_account_metas_account(hook_program, &[extra_account])),
+        ]);
+
+        let client = EscrowClient::new(accounts);
+
+        let ix = client.make_ix(&maker, &hooked_mint, &mint_b, 1, 100, 50, Lock::default()).unwrap();
+
+        // Appended after the instruction's own accounts, in the order the hook expects them
+        let keys: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+        assert_eq!(keys[keys.len() - 3..], [extra_account, hook_program, validation_address]);
+
+        // Mint B only moves on take, so it adds nothing here
+        let ix = client.make_ix(&maker, &mint_b, &hooked_mint, 1, 100, 50, Lock::default()).unwrap();
+        assert!(!ix.accounts.iter().any(|meta| meta.pubkey == hook_program), "No hook accounts for mint B on make");
+
+        let ix = client.make_ix(&maker, &unhooked_mint, &mint_b, 1, 100, 50, Lock::default()).unwrap();
+        assert!(!ix.accounts.iter().any(|meta| meta.pubkey == validation_address), "A hook extension without a program needs no accounts");
+    }
+
+    #[test]
+    fn test_make_ix_requires_transfer_hook_validation_account() {
+        let (maker, hooked_mint, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+
+        let accounts = HashMap::from([
+            (hooked_mint, transfer_hook_mint_account(Some(Pubkey::new_unique()))),
+            (mint_b, mint_account(spl_token::ID)),
+        ]);
+
+        let result = EscrowClient::new(accounts).make_ix(&maker, &hooked_mint, &mint_b, 1, 100, 50, Lock::default());
+
+        assert!(matches!(result, Err(ClientError::TransferHook(address, _)) if address == hooked_mint));
+    }
+
+    #[test]
+    fn test_take_and_refund_ix_resolve_transfer_hook_accounts() {
+        let (maker, taker, treasury, hook_program) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let (mint_a, mint_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let (extra_account_a, extra_account_b) = (Pubkey::new_unique(), Pubkey::new_unique());
+
+        let accounts = HashMap::from([
+            (mint_a, transfer_hook_mint_account(Some(hook_program))),
+            (mint_b, transfer_hook_mint_account(Some(hook_program))),
+            (get_extra_account_metas_address(&mint_a, &hook_program), extra_account_metas_account(hook_program, &[extra_account_a])),
+            (get_extra_account_metas_address(&mint_b, &hook_program), extra_account_metas_account(hook_program, &[extra_account_b])),
+            (find_config_address(), program_account(&config(treasury, 100))),
+            (find_escrow_address(&maker, 3), program_account(&escrow(maker, 3, mint_a, mint_b))),
+        ]);
+
+        let client = EscrowClient::new(accounts);
+
+        // Both legs of the take move a hooked mint
+        let ix = client.take_ix(&taker, &maker, 3).unwrap();
+        let keys: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+        assert!(keys.contains(&extra_account_a), "Mint A hook accounts should be appended");
+        assert!(keys.contains(&extra_account_b), "Mint B hook accounts should be appended");
+
+        let ix = client.refund_ix(&maker, 3).unwrap();
+        let keys: Vec<Pubkey> = ix.accounts.iter().map(|meta| meta.pubkey).collect();
+        assert!(keys.contains(&extra_account_a), "Mint A hook accounts should be appended");
+        assert!(!keys.contains(&extra_account_b), "Mint B does not move on refund");
+    }
+}
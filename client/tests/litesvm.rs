@@ -0,0 +1,62 @@
+// Instructions built by `EscrowClient` from accounts read out of LiteSVM, run against the
+// program built at `target/deploy/anchor_escrow.so`.
+
+use {
+    anchor_escrow_client::{find_escrow_address, AccountFetcher, ClientError, EscrowClient, FetchedAccount, Lock},
+    anchor_escrow_harness::{send, EscrowTestEnv},
+    anchor_lang::prelude::Pubkey,
+    litesvm::LiteSVM,
+    solana_signer::Signer,
+};
+
+struct Svm<'a>(&'a LiteSVM);
+
+impl AccountFetcher for Svm<'_> {
+    fn fetch_account(&self, address: &Pubkey) -> Result<FetchedAccount, ClientError> {
+        let account = self.0.get_account(address).ok_or(ClientError::AccountNotFound(*address))?;
+
+        Ok(FetchedAccount { owner: account.owner, data: account.data })
+    }
+}
+
+fn client(program: &LiteSVM) -> EscrowClient<Svm<'_>> {
+    EscrowClient::new(Svm(program))
+}
+
+#[test]
+fn test_make_take_and_refund() {
+    let mut env = EscrowTestEnv::new().with_mints(6);
+    let taker = env.new_taker();
+
+    let (maker, mint_a, mint_b) = (env.maker.pubkey(), env.mint_a, env.mint_b);
+    let starting_a = env.balance_a(&maker);
+
+    // Make and take in full: the taker's mint A and the maker's mint B accounts are created by the take
+    let make_ix = client(&env.program).make_ix(&maker, &mint_a, &mint_b, 1, 100, 50, Lock::default()).unwrap();
+    send(&mut env.program, make_ix, &env.maker).unwrap();
+
+    assert_eq!(env.balance_a(&maker), starting_a - 100);
+
+    let take_ix = client(&env.program).take_ix(&taker.pubkey(), &maker, 1).unwrap();
+    send(&mut env.program, take_ix, &taker).unwrap();
+
+    assert_eq!(env.balance_a(&taker.pubkey()), 100);
+    assert_eq!(env.balance_b(&maker), 50);
+    assert!(!env.exists(&find_escrow_address(&maker, 1)), "Escrow should be closed");
+
+    // Partly take a second escrow, then refund what is left
+    let make_ix = client(&env.program).make_ix(&maker, &mint_a, &mint_b, 2, 100, 50, Lock::default()).unwrap();
+    send(&mut env.program, make_ix, &env.maker).unwrap();
+
+    let take_partial_ix = client(&env.program).take_partial_ix(&taker.pubkey(), &maker, 2, 20).unwrap();
+    send(&mut env.program, take_partial_ix, &taker).unwrap();
+
+    assert_eq!(env.balance_a(&taker.pubkey()), 140);
+    assert_eq!(env.balance_b(&maker), 70);
+
+    let refund_ix = client(&env.program).refund_ix(&maker, 2).unwrap();
+    send(&mut env.program, refund_ix, &env.maker).unwrap();
+
+    assert_eq!(env.balance_a(&maker), starting_a - 140);
+    assert!(!env.exists(&find_escrow_address(&maker, 2)), "Escrow should be closed");
+}
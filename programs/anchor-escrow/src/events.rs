@@ -0,0 +1,109 @@
+use anchor_lang::prelude::*;
+
+use crate::state::BasketLeg;
+
+#[event]
+pub struct EscrowMade {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub deposit: u64, // Mint A actually received by the vault
+    pub receive: u64, // Mint B requested in return
+    pub slot: u64,
+}
+
+#[event]
+pub struct EscrowTaken {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub amount_a: u64, // Mint A released to the taker by this fill
+    pub amount_b: u64, // Mint B paid by the taker for this fill
+    pub protocol_fee: u64, // Part of amount_b sent to the treasury instead of the maker
+    pub referrer: Option<Pubkey>, // Owner of the referrer account passed to take, if any
+    pub referrer_fee: u64, // Paid by the taker to the referrer on top of amount_b
+    pub remaining_receive: u64, // Zero once the escrow is fully taken and closed
+    pub slot: u64,
+}
+
+#[event]
+pub struct EscrowUpdated {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub deposit: u64, // Mint A now held in the vault
+    pub receive: u64, // Mint B now requested in return
+    pub expires_at: Option<i64>,
+    pub slot: u64,
+}
+
+#[event]
+pub struct EscrowRefunded {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub amount: u64, // Mint A returned to the maker
+    pub slot: u64,
+}
+
+#[event]
+pub struct BasketMade {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub deposits: Vec<BasketLeg>, // Amounts actually received by each vault
+    pub requests: Vec<BasketLeg>,
+    pub slot: u64,
+}
+
+#[event]
+pub struct BasketTaken {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub taker: Pubkey,
+    pub deposits: Vec<BasketLeg>, // Released to the taker
+    pub requests: Vec<BasketLeg>, // Paid by the taker, protocol fee included
+    pub slot: u64,
+}
+
+#[event]
+pub struct BasketRefunded {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub deposits: Vec<BasketLeg>, // Returned to the maker
+    pub slot: u64,
+}
+
+#[event]
+pub struct BidPlaced {
+    pub escrow: Pubkey,
+    pub bidder: Pubkey,
+    pub mint_b: Pubkey,
+    pub amount_a: u64, // Mint A asked for out of the escrow
+    pub amount_b: u64, // Mint B actually received by the bid vault
+    pub slot: u64,
+}
+
+#[event]
+pub struct BidAccepted {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub bidder: Pubkey,
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub amount_a: u64, // Mint A released to the bidder
+    pub amount_b: u64, // Mint B released from the bid vault
+    pub protocol_fee: u64, // Part of amount_b sent to the treasury instead of the maker
+    pub remaining_receive: u64, // Zero once the escrow is fully sold and closed
+    pub slot: u64,
+}
+
+#[event]
+pub struct BidCancelled {
+    pub escrow: Pubkey,
+    pub bidder: Pubkey,
+    pub amount_b: u64, // Mint B returned to the bidder
+    pub slot: u64,
+}
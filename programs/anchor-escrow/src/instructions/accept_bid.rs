@@ -0,0 +1,255 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, CloseAccount, close_account}};
+
+use crate::events::BidAccepted;
+use crate::state::{Bid, Config, Escrow};
+use crate::utils::{close_vault_and_escrow, harvest_withheld_fees, release_from_vault, transfer_checked_with_hook};
+use crate::EscrowError;
+
+#[derive(Accounts)]
+pub struct AcceptBid<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(mut)]
+    pub bidder: SystemAccount<'info>,
+    #[account(
+        mut,
+        mint::token_program = token_program_a,
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        mint::token_program = token_program_b,
+    )]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    // Omitted for a native SOL deposit, which reaches the bidder as lamports
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_a,
+        associated_token::authority = bidder,
+        associated_token::token_program = token_program_a,
+    )]
+    pub bidder_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+    // Only for a native SOL deposit: the bidder does not sign, so the maker unwraps
+    // their share through this account and forwards the lamports
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_a,
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_b,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program_b,
+    )]
+    pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: Only receives the protocol fee, pinned to the configured treasury
+    #[account(
+        address = config.treasury,
+    )]
+    pub treasury: UncheckedAccount<'info>,
+    // Only needed when the protocol fee is non-zero
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_b,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program_b,
+    )]
+    pub treasury_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        has_one = maker,
+        has_one = mint_a,
+        has_one = mint_b,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program_a,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = bidder,
+        has_one = escrow,
+        has_one = bidder,
+        seeds = [b"bid", escrow.key().as_ref(), bidder.key().as_ref()],
+        bump = bid.bump,
+    )]
+    pub bid: Account<'info, Bid>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = bid,
+        associated_token::token_program = token_program_b,
+    )]
+    pub bid_vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+//Pay the maker out of the bid vault and close it
+//Release the bid's share of the escrow vault to the bidder
+//Close vault and escrow accounts once the whole deposit is sold
+impl<'info> AcceptBid<'info> {
+    // Returns the protocol fee withheld from the maker's proceeds
+    pub fn settle_bid(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<u64> {
+        require!(!self.config.paused, EscrowError::Paused);
+
+        // Partial fills since the bid was placed may have left less than it asks for
+        require!(self.bid.amount_a <= self.escrow.deposit, EscrowError::InvalidFillAmount);
+        require!(
+            self.escrow.auction.is_none() || self.bid.amount_a == self.escrow.deposit,
+            EscrowError::AuctionNotDivisible
+        );
+
+        let fee = self.config.protocol_fee(self.bid.amount_b)?;
+        let maker_amount = self.bid.amount_b.checked_sub(fee).ok_or(EscrowError::Overflow)?;
+
+        self.pay_from_bid_vault(&self.maker_ata_b, maker_amount, remaining_accounts)?;
+
+        if fee > 0 {
+            let Some(treasury_ata_b) = &self.treasury_ata_b else {
+                return err!(EscrowError::MissingTokenAccount);
+            };
+
+            self.pay_from_bid_vault(treasury_ata_b, fee, remaining_accounts)?;
+        }
+
+        harvest_withheld_fees(&self.token_program_b, &self.mint_b, &self.bid_vault)?;
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"bid",
+            self.bid.escrow.as_ref(),
+            self.bid.bidder.as_ref(),
+            &[self.bid.bump]
+        ]];
+
+        let cpi_program = self.token_program_b.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.bid_vault.to_account_info(),
+            destination: self.bidder.to_account_info(),
+            authority: self.bid.to_account_info(),
+        };
+
+        close_account(CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds))?;
+
+        Ok(fee)
+    }
+
+    fn pay_from_bid_vault(&self, to: &InterfaceAccount<'info, TokenAccount>, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"bid",
+            self.bid.escrow.as_ref(),
+            self.bid.bidder.as_ref(),
+            &[self.bid.bump]
+        ]];
+
+        let cpi_program = self.token_program_b.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.bid_vault.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.bid.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_b.decimals)
+    }
+
+    pub fn release_deposit(&mut self, fee: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let amount_a = self.bid.amount_a;
+        let amount_b = self.escrow.share_of_receive(amount_a);
+
+        let is_final_fill = amount_a == self.escrow.deposit;
+
+        let withdraw_amount = if self.vault.is_native() {
+            let Some(maker_ata_a) = &self.maker_ata_a else {
+                return err!(EscrowError::MissingTokenAccount);
+            };
+
+            let withdraw_amount = release_from_vault(
+                self.maker.as_ref(),
+                &self.mint_a,
+                &self.escrow,
+                &self.vault,
+                maker_ata_a,
+                self.maker.to_account_info(),
+                &self.token_program_a,
+                amount_a,
+                is_final_fill,
+                remaining_accounts,
+            )?;
+
+            let cpi_accounts = Transfer {
+                from: self.maker.to_account_info(),
+                to: self.bidder.to_account_info(),
+            };
+
+            transfer(CpiContext::new(self.system_program.to_account_info(), cpi_accounts), withdraw_amount)?;
+
+            withdraw_amount
+        } else {
+            let Some(bidder_ata_a) = &self.bidder_ata_a else {
+                return err!(EscrowError::MissingTokenAccount);
+            };
+
+            release_from_vault(
+                self.maker.as_ref(),
+                &self.mint_a,
+                &self.escrow,
+                &self.vault,
+                bidder_ata_a,
+                self.bidder.to_account_info(),
+                &self.token_program_a,
+                amount_a,
+                is_final_fill,
+                remaining_accounts,
+            )?
+        };
+
+        self.escrow.deposit = self.escrow.deposit.checked_sub(amount_a).ok_or(EscrowError::Overflow)?;
+        self.escrow.receive = self.escrow.receive.checked_sub(amount_b).ok_or(EscrowError::Overflow)?;
+
+        emit!(BidAccepted {
+            seed: self.escrow.seed,
+            maker: self.maker.key(),
+            bidder: self.bidder.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            amount_a: withdraw_amount,
+            amount_b: self.bid.amount_b,
+            protocol_fee: fee,
+            remaining_receive: self.escrow.receive,
+            slot: Clock::get()?.slot,
+        });
+
+        if !is_final_fill {
+            return Ok(());
+        }
+
+        close_vault_and_escrow(self.maker.as_ref(), &self.mint_a, &self.escrow, &self.vault, &self.token_program_a)
+    }
+}
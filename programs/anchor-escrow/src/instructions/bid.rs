@@ -0,0 +1,121 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked}};
+
+use crate::events::BidPlaced;
+use crate::state::{Bid, Config, Escrow};
+use crate::utils::transfer_checked_with_hook;
+use crate::EscrowError;
+
+#[derive(Accounts)]
+pub struct PlaceBid<'info> {
+    #[account(mut)]
+    pub bidder: Signer<'info>,
+    pub maker: SystemAccount<'info>,
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = bidder,
+        associated_token::token_program = token_program,
+    )]
+    pub bidder_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        has_one = maker,
+        has_one = mint_b,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        init,
+        payer = bidder,
+        seeds = [b"bid", escrow.key().as_ref(), bidder.key().as_ref()],
+        bump,
+        space = 8 + Bid::INIT_SPACE,
+    )]
+    pub bid: Account<'info, Bid>,
+    #[account(
+        init,
+        payer = bidder,
+        associated_token::mint = mint_b,
+        associated_token::authority = bid,
+        associated_token::token_program = token_program,
+    )]
+    pub bid_vault: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+//Lock the bidder's mint B in the bid vault until the maker accepts or the bidder cancels
+impl<'info> PlaceBid<'info> {
+    pub fn place_bid(&mut self, amount_a: u64, amount_b: u64, remaining_accounts: &[AccountInfo<'info>], bumps: &PlaceBidBumps) -> Result<()> {
+        require!(!self.config.paused, EscrowError::Paused);
+
+        require!(
+            self.escrow.can_be_taken_by(self.bidder.key),
+            EscrowError::UnauthorizedTaker
+        );
+
+        let clock = Clock::get()?;
+        require!(!self.escrow.is_expired(self.escrow.now(&clock)), EscrowError::EscrowExpired);
+
+        require!(
+            amount_a > 0 && amount_a <= self.escrow.deposit,
+            EscrowError::InvalidFillAmount
+        );
+        require!(amount_b > 0, EscrowError::ZeroDeposit);
+
+        // Selling part of an auction would leave its price curve out of step with the deposit
+        require!(
+            self.escrow.auction.is_none() || amount_a == self.escrow.deposit,
+            EscrowError::AuctionNotDivisible
+        );
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.bidder_ata_b.to_account_info(),
+            to: self.bid_vault.to_account_info(),
+            authority: self.bidder.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked_with_hook(cpi_ctx, amount_b, self.mint_b.decimals)?;
+
+        // Record what the vault actually received, net of any Token-2022 transfer fee
+        self.bid_vault.reload()?;
+        require!(self.bid_vault.amount > 0, EscrowError::ZeroDeposit);
+
+        self.bid.set_inner(Bid {
+            escrow: self.escrow.key(),
+            bidder: self.bidder.key(),
+            mint_b: self.mint_b.key(),
+            amount_a,
+            amount_b: self.bid_vault.amount,
+            bump: bumps.bid,
+        });
+
+        emit!(BidPlaced {
+            escrow: self.escrow.key(),
+            bidder: self.bidder.key(),
+            mint_b: self.mint_b.key(),
+            amount_a,
+            amount_b: self.bid.amount_b,
+            slot: clock.slot,
+        });
+
+        Ok(())
+    }
+}
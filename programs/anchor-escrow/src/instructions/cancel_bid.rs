@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, CloseAccount, close_account};
+
+use crate::events::BidCancelled;
+use crate::state::Bid;
+use crate::utils::{harvest_withheld_fees, transfer_checked_with_hook};
+
+// Does not touch the escrow, so a bid can still be withdrawn after the escrow is taken or refunded
+#[derive(Accounts)]
+pub struct CancelBid<'info> {
+    #[account(mut)]
+    bidder: Signer<'info>,
+    #[account(
+        mut,
+        mint::token_program = token_program,
+    )]
+    mint_b: InterfaceAccount<'info, Mint>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = bidder,
+        associated_token::token_program = token_program,
+    )]
+    bidder_ata_b: InterfaceAccount<'info, TokenAccount>,
+    #[account(
+        mut,
+        close = bidder,
+        has_one = bidder,
+        has_one = mint_b,
+        seeds = [b"bid", bid.escrow.as_ref(), bidder.key().as_ref()],
+        bump = bid.bump,
+    )]
+    pub bid: Account<'info, Bid>,
+    #[account(
+        mut,
+        associated_token::mint = mint_b,
+        associated_token::authority = bid,
+        associated_token::token_program = token_program,
+    )]
+    bid_vault: InterfaceAccount<'info, TokenAccount>,
+    token_program: Interface<'info, TokenInterface>,
+    system_program: Program<'info, System>,
+}
+
+impl<'info> CancelBid<'info> {
+    pub fn refund_and_close_bid_vault(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"bid",
+            self.bid.escrow.as_ref(),
+            self.bid.bidder.as_ref(),
+            &[self.bid.bump]
+        ]];
+
+        emit!(BidCancelled {
+            escrow: self.bid.escrow,
+            bidder: self.bidder.key(),
+            amount_b: self.bid_vault.amount,
+            slot: Clock::get()?.slot,
+        });
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: self.bid_vault.to_account_info(),
+            to: self.bidder_ata_b.to_account_info(),
+            mint: self.mint_b.to_account_info(),
+            authority: self.bid.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked_with_hook(cpi_context, self.bid_vault.amount, self.mint_b.decimals)?;
+
+        harvest_withheld_fees(&self.token_program, &self.mint_b, &self.bid_vault)?;
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: self.bid_vault.to_account_info(),
+            destination: self.bidder.to_account_info(),
+            authority: self.bid.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+        close_account(cpi_context)
+    }
+}
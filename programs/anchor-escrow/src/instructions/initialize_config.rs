@@ -0,0 +1,48 @@
+use anchor_lang::prelude::*;
+
+use crate::program::AnchorEscrow;
+use crate::state::{valid_fee_bps, Config};
+use crate::EscrowError;
+
+// Run once right after deployment by the program's upgrade authority, who becomes the config admin
+#[derive(Accounts)]
+pub struct InitializeConfig<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        constraint = program.programdata_address()? == Some(program_data.key()),
+    )]
+    pub program: Program<'info, AnchorEscrow>,
+    #[account(
+        constraint = program_data.upgrade_authority_address == Some(admin.key()) @ EscrowError::NotUpgradeAuthority,
+    )]
+    pub program_data: Account<'info, ProgramData>,
+    #[account(
+        init,
+        payer = admin,
+        seeds = [b"config"],
+        bump,
+        space = 8 + Config::INIT_SPACE,
+    )]
+    pub config: Account<'info, Config>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> InitializeConfig<'info> {
+    pub fn initialize_config(&mut self, fee_bps: u16, max_referrer_fee_bps: u16, treasury: Pubkey, bumps: &InitializeConfigBumps) -> Result<()> {
+        require!(valid_fee_bps(fee_bps, max_referrer_fee_bps), EscrowError::InvalidFeeBps);
+
+        self.config.set_inner(Config {
+            admin: self.admin.key(),
+            treasury,
+            fee_bps,
+            max_referrer_fee_bps,
+            paused: false,
+            allowlist_only: false,
+            reject_risky_mints: false,
+            bump: bumps.config,
+        });
+
+        Ok(())
+    }
+}
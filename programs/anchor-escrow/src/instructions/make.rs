@@ -1,21 +1,32 @@
 use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked}};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked}};
 
-use crate::state::Escrow;
+use crate::events::EscrowMade;
+use crate::state::{Config, DutchAuction, Escrow, LockKind, NftTerms};
+use crate::utils::{check_mint_policy, is_collection, is_native_mint, is_nft, transfer_checked_with_hook, wrap_sol};
+use crate::EscrowError;
 
 #[derive(Accounts)]
 #[instruction(seed: u64)]
 pub struct Make<'info> {
     #[account(mut)]
     pub maker: Signer<'info>,
+    #[account(
+        mint::token_program = token_program_a,
+    )]
     pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mint::token_program = token_program_b,
+    )]
     pub mint_b: InterfaceAccount<'info, Mint>,
+    // Omitted when mint A is native SOL and the maker deposits lamports directly
     #[account(
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = maker,
+        associated_token::token_program = token_program_a,
     )]
-    pub maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init,
         payer = maker,
@@ -24,20 +35,66 @@ pub struct Make<'info> {
         space = 8 + Escrow::INIT_SPACE,
     )]
     pub escrow: Account<'info, Escrow>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: Registry entry for mint A, may not exist. Required so a denied mint cannot be bypassed
+    #[account(
+        seeds = [b"mint", mint_a.key().as_ref()],
+        bump,
+    )]
+    pub mint_a_entry: UncheckedAccount<'info>,
+    /// CHECK: Registry entry for mint B, may not exist. Required so a denied mint cannot be bypassed
+    #[account(
+        seeds = [b"mint", mint_b.key().as_ref()],
+        bump,
+    )]
+    pub mint_b_entry: UncheckedAccount<'info>,
     #[account(
         init,
         payer = maker,
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
+        associated_token::token_program = token_program_a,
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
     pub associated_token_program: Program<'info, AssociatedToken>,
-    pub token_program: Interface<'info, TokenInterface>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
 impl<'info> Make<'info> {
-    pub fn init_escrow(&mut self, seed: u64, receive: u64, lock_period: i64, bumps: &MakeBumps) -> Result<()> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn init_escrow(&mut self, seed: u64, receive: u64, lock_kind: LockKind, lock_period: i64, commit_period: i64, expires_at: Option<i64>, taker: Option<Pubkey>, nft: NftTerms, auction: Option<DutchAuction>, bumps: &MakeBumps) -> Result<()> {
+        require!(!self.config.paused, EscrowError::Paused);
+
+        require!(receive > 0, EscrowError::ZeroReceive);
+        require!(lock_period >= 0 && commit_period >= 0, EscrowError::NegativeLock);
+        require_keys_neq!(self.mint_a.key(), self.mint_b.key(), EscrowError::SameMint);
+
+        check_mint_policy(&self.config, &self.mint_a, &self.mint_a_entry)?;
+        check_mint_policy(&self.config, &self.mint_b, &self.mint_b_entry)?;
+
+        if nft.deposit_is_nft {
+            require!(is_nft(&self.mint_a), EscrowError::NotAnNft);
+        }
+
+        // Collection offers are filled by one member NFT, never split across takers
+        if nft.any_from_collection {
+            require!(receive == 1 && is_collection(&self.mint_b)?, EscrowError::InvalidCollection);
+        }
+
+        // `receive` is the start price
+        if let Some(auction) = auction {
+            require!(
+                auction.duration > 0 && auction.end_receive > 0 && auction.end_receive <= receive,
+                EscrowError::InvalidAuction
+            );
+        }
+
         let clock = Clock::get()?;
 
         self.escrow.set_inner(Escrow {
@@ -45,29 +102,78 @@ impl<'info> Make<'info> {
             maker: self.maker.key(),
             mint_a: self.mint_a.key(),
             mint_b: self.mint_b.key(),
+            deposit: 0,
             receive,
             bump: bumps.escrow,
-            start_time: clock.slot as i64,
+            lock_kind,
+            start_time: lock_kind.now(&clock),
             lock_period,
+            commit_period,
+            expires_at,
+            taker,
+            nft,
+            auction,
         });
 
+        // An end that cannot be represented would leave the escrow untakeable or unrefundable
+        let lock_ends_at = self.escrow.lock_ends_at()?;
+        self.escrow.commit_ends_at()?;
+
+        // An offer expiring by the time it unlocks could never be taken, only closed by anyone
+        if let Some(expires_at) = expires_at {
+            require!(expires_at > lock_ends_at, EscrowError::InvalidExpiry);
+        }
+
         Ok(())
     }
 
-    pub fn deposit(&mut self, deposit: u64) -> Result<()> {
-        let cpi_program = self.token_program.to_account_info();
+    pub fn deposit(&mut self, deposit: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(deposit > 0, EscrowError::ZeroDeposit);
+
+        if self.escrow.nft.deposit_is_nft {
+            require!(deposit == 1, EscrowError::NotAnNft);
+        }
+
+        match &self.maker_ata_a {
+            Some(maker_ata_a) => {
+                let cpi_program = self.token_program_a.to_account_info();
+
+                let cpi_accounts = TransferChecked {
+                    from: maker_ata_a.to_account_info(),
+                    to: self.vault.to_account_info(),
+                    authority: self.maker.to_account_info(),
+                    mint: self.mint_a.to_account_info(),
+                };
+
+                let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+                    .with_remaining_accounts(remaining_accounts.to_vec());
 
-        let cpi_accounts = TransferChecked {
-            from: self.maker_ata_a.to_account_info(),
-            to: self.vault.to_account_info(),
-            authority: self.maker.to_account_info(),
-            mint: self.mint_a.to_account_info(),
-        };
+                transfer_checked_with_hook(cpi_ctx, deposit, self.mint_a.decimals)?;
+            }
+            None => {
+                require!(is_native_mint(&self.mint_a.key()), EscrowError::MissingTokenAccount);
 
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+                wrap_sol(&self.system_program, self.maker.to_account_info(), &self.token_program_a, &self.vault, deposit)?;
+            }
+        }
 
-        transfer_checked(cpi_ctx, deposit, self.mint_a.decimals)?;
+        // Record what the vault actually received, net of any Token-2022 transfer fee
+        self.vault.reload()?;
+        self.escrow.deposit = self.vault.amount;
+
+        // A transfer fee can swallow a dust deposit entirely
+        require!(self.escrow.deposit > 0, EscrowError::ZeroDeposit);
+
+        emit!(EscrowMade {
+            seed: self.escrow.seed,
+            maker: self.maker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            deposit: self.escrow.deposit,
+            receive: self.escrow.receive,
+            slot: Clock::get()?.slot,
+        });
 
         Ok(())
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,121 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount}};
+
+use crate::events::BasketMade;
+use crate::state::{Basket, BasketLeg, Config};
+use crate::utils::{check_leg_mint_policy, create_ata_if_needed, load_leg_mint, require_no_transfer_hook, transfer_leg, validate_basket_legs};
+use crate::EscrowError;
+
+// Remaining accounts per deposit leg: mint, mint registry entry, maker ATA, vault, token program
+pub const MAKE_DEPOSIT_LEG_ACCOUNTS: usize = 5;
+// Remaining accounts per request leg: mint, mint registry entry
+pub const MAKE_REQUEST_LEG_ACCOUNTS: usize = 2;
+
+#[derive(Accounts)]
+#[instruction(seed: u64)]
+pub struct MakeBasket<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        init,
+        payer = maker,
+        seeds = [b"basket", maker.key().as_ref(), seed.to_le_bytes().as_ref()],
+        bump,
+        space = 8 + Basket::INIT_SPACE,
+    )]
+    pub basket: Account<'info, Basket>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> MakeBasket<'info> {
+    pub fn make_basket(
+        &mut self,
+        seed: u64,
+        deposits: Vec<BasketLeg>,
+        requests: Vec<BasketLeg>,
+        remaining_accounts: &'info [AccountInfo<'info>],
+        bumps: &MakeBasketBumps,
+    ) -> Result<()> {
+        require!(!self.config.paused, EscrowError::Paused);
+
+        validate_basket_legs(&deposits, &requests)?;
+
+        let deposit_accounts_len = deposits.len() * MAKE_DEPOSIT_LEG_ACCOUNTS;
+        require!(
+            remaining_accounts.len() == deposit_accounts_len + requests.len() * MAKE_REQUEST_LEG_ACCOUNTS,
+            EscrowError::InvalidBasketAccounts
+        );
+
+        let (deposit_accounts, request_accounts) = remaining_accounts.split_at(deposit_accounts_len);
+
+        for (leg, accounts) in requests.iter().zip(request_accounts.chunks(MAKE_REQUEST_LEG_ACCOUNTS)) {
+            let [mint, mint_entry] = accounts else {
+                return err!(EscrowError::InvalidBasketAccounts);
+            };
+
+            require_keys_eq!(mint.key(), leg.mint, EscrowError::InvalidBasketAccounts);
+            let mint = InterfaceAccount::<Mint>::try_from(mint)?;
+
+            check_leg_mint_policy(&self.config, &mint, mint_entry)?;
+            require_no_transfer_hook(&mint)?;
+        }
+
+        let mut received = Vec::with_capacity(deposits.len());
+
+        for (leg, accounts) in deposits.iter().zip(deposit_accounts.chunks(MAKE_DEPOSIT_LEG_ACCOUNTS)) {
+            let [mint, mint_entry, maker_ata, vault, token_program] = accounts else {
+                return err!(EscrowError::InvalidBasketAccounts);
+            };
+
+            let (mint, token_program) = load_leg_mint(mint, token_program, &leg.mint)?;
+
+            check_leg_mint_policy(&self.config, &mint, mint_entry)?;
+            require_no_transfer_hook(&mint)?;
+
+            create_ata_if_needed(
+                &self.associated_token_program,
+                &self.system_program,
+                self.maker.to_account_info(),
+                vault.clone(),
+                self.basket.to_account_info(),
+                &mint,
+                &token_program,
+            )?;
+
+            transfer_leg(&token_program, &mint, maker_ata.clone(), vault.clone(), self.maker.to_account_info(), leg.amount, &[])?;
+
+            // Record what the vault actually received, net of any Token-2022 transfer fee
+            let vault = InterfaceAccount::<TokenAccount>::try_from(vault)?;
+            require!(vault.amount > 0, EscrowError::ZeroDeposit);
+
+            received.push(BasketLeg {
+                mint: leg.mint,
+                amount: vault.amount,
+            });
+        }
+
+        self.basket.set_inner(Basket {
+            seed,
+            maker: self.maker.key(),
+            bump: bumps.basket,
+            deposits: received,
+            requests,
+        });
+
+        emit!(BasketMade {
+            seed,
+            maker: self.maker.key(),
+            deposits: self.basket.deposits.clone(),
+            requests: self.basket.requests.clone(),
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+}
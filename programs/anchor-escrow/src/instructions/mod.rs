@@ -1,7 +1,29 @@
+pub mod accept_bid;
+pub mod bid;
+pub mod cancel_bid;
+pub mod initialize_config;
 pub mod make;
+pub mod make_basket;
 pub mod refund;
+pub mod refund_basket;
+pub mod refund_expired;
+pub mod set_mint_status;
 pub mod take;
+pub mod take_basket;
+pub mod update;
+pub mod update_config;
 
+pub use accept_bid::*;
+pub use bid::*;
+pub use cancel_bid::*;
+pub use initialize_config::*;
 pub use make::*;
+pub use make_basket::*;
 pub use refund::*;
-pub use take::*;
\ No newline at end of file
+pub use refund_basket::*;
+pub use refund_expired::*;
+pub use set_mint_status::*;
+pub use take::*;
+pub use take_basket::*;
+pub use update::*;
+pub use update_config::*;
@@ -1,19 +1,26 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token_interface::{transfer_checked, Mint, TokenAccount, TokenInterface, TransferChecked, CloseAccount, close_account};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
 use crate::state::Escrow;
+use crate::utils::return_vault_to_maker;
+use crate::EscrowError;
 
 #[derive(Accounts)]
 pub struct Refund<'info> {
     #[account(mut)]
     maker: Signer<'info>,
+    #[account(
+        mut,
+        mint::token_program = token_program,
+    )]
     mint_a: InterfaceAccount<'info, Mint>,
     #[account(
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = maker,
+        associated_token::token_program = token_program,
     )]
-    maker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
         close = maker,
@@ -27,6 +34,7 @@ pub struct Refund<'info> {
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
+        associated_token::token_program = token_program,
     )]
     vault: InterfaceAccount<'info, TokenAccount>,
     token_program: Interface<'info, TokenInterface>,
@@ -34,39 +42,24 @@ pub struct Refund<'info> {
 }
 
 impl<'info> Refund<'info> {
-    pub fn refund_and_close_vault(&mut self) -> Result<()> {
-        let signer_seeds: [&[&[u8]]; 1] = [&[
-            b"escrow",
-            self.maker.key.as_ref(),
-            &self.escrow.seed.to_le_bytes()[..],
-            &[self.escrow.bump]
-        ]];
-        
-        let cpi_program = self.token_program.to_account_info();
-
-        let cpi_accounts = TransferChecked {
-            from: self.vault.to_account_info(),
-            to: self.maker_ata_a.to_account_info(),
-            mint: self.mint_a.to_account_info(),
-            authority: self.escrow.to_account_info(),
-        };
-
-        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
-
-        transfer_checked(cpi_context, self.vault.amount, self.mint_a.decimals)?;
-
-        let cpi_program = self.token_program.to_account_info();
-
-        let cpi_accounts = CloseAccount {
-            account: self.vault.to_account_info(),
-            destination: self.maker.to_account_info(),
-            authority: self.escrow.to_account_info(),
-        };
+    pub fn refund_and_close_vault(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // Check if commit period has elapsed
+        let clock = Clock::get()?;
+        let now = self.escrow.now(&clock);
 
-        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+        require!(
+            now >= self.escrow.commit_ends_at()?,
+            EscrowError::RefundLocked
+        );
 
-        close_account(cpi_context)?;
-        
-        Ok(())
+        return_vault_to_maker(
+            self.maker.to_account_info(),
+            &self.mint_a,
+            self.maker_ata_a.as_ref(),
+            &self.escrow,
+            &self.vault,
+            &self.token_program,
+            remaining_accounts,
+        )
     }
 }
\ No newline at end of file
@@ -0,0 +1,80 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+
+use crate::events::BasketRefunded;
+use crate::state::Basket;
+use crate::utils::{create_ata_if_needed, drain_and_close_vault, load_leg_mint, load_leg_vault};
+use crate::EscrowError;
+
+// Remaining accounts per deposit leg: mint, vault, maker ATA, token program
+pub const REFUND_DEPOSIT_LEG_ACCOUNTS: usize = 4;
+
+#[derive(Accounts)]
+pub struct RefundBasket<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [b"basket", maker.key().as_ref(), basket.seed.to_le_bytes().as_ref()],
+        bump = basket.bump,
+    )]
+    pub basket: Account<'info, Basket>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RefundBasket<'info> {
+    pub fn refund_and_close_vaults(&mut self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        require!(
+            remaining_accounts.len() == self.basket.deposits.len() * REFUND_DEPOSIT_LEG_ACCOUNTS,
+            EscrowError::InvalidBasketAccounts
+        );
+
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"basket",
+            self.maker.key.as_ref(),
+            &self.basket.seed.to_le_bytes()[..],
+            &[self.basket.bump]
+        ]];
+
+        for (leg, accounts) in self.basket.deposits.iter().zip(remaining_accounts.chunks(REFUND_DEPOSIT_LEG_ACCOUNTS)) {
+            let [mint, vault, maker_ata, token_program] = accounts else {
+                return err!(EscrowError::InvalidBasketAccounts);
+            };
+
+            let (mint, token_program) = load_leg_mint(mint, token_program, &leg.mint)?;
+            let vault = load_leg_vault(vault, &self.basket.key(), &mint, &token_program)?;
+
+            create_ata_if_needed(
+                &self.associated_token_program,
+                &self.system_program,
+                self.maker.to_account_info(),
+                maker_ata.clone(),
+                self.maker.to_account_info(),
+                &mint,
+                &token_program,
+            )?;
+
+            drain_and_close_vault(
+                &token_program,
+                &mint,
+                &vault,
+                maker_ata.clone(),
+                self.basket.to_account_info(),
+                self.maker.to_account_info(),
+                &signer_seeds,
+            )?;
+        }
+
+        emit!(BasketRefunded {
+            seed: self.basket.seed,
+            maker: self.maker.key(),
+            deposits: self.basket.deposits.clone(),
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface}};
+
+use crate::state::Escrow;
+use crate::utils::return_vault_to_maker;
+use crate::EscrowError;
+
+// Permissionless refund: once an escrow has expired anyone may return
+// the vault to the maker and close it
+#[derive(Accounts)]
+pub struct RefundExpired<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    #[account(
+        mut,
+        mint::token_program = token_program,
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        init_if_needed,
+        payer = payer,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = mint_a,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> RefundExpired<'info> {
+    pub fn refund_and_close_vault(&mut self, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let clock = Clock::get()?;
+        let now = self.escrow.now(&clock);
+
+        require!(self.escrow.is_expired(now), EscrowError::EscrowNotExpired);
+
+        return_vault_to_maker(
+            self.maker.to_account_info(),
+            &self.mint_a,
+            self.maker_ata_a.as_ref(),
+            &self.escrow,
+            &self.vault,
+            &self.token_program,
+            remaining_accounts,
+        )
+    }
+}
@@ -0,0 +1,37 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{Config, MintEntry, MintStatus};
+
+#[derive(Accounts)]
+#[instruction(mint: Pubkey)]
+pub struct SetMintStatus<'info> {
+    #[account(mut)]
+    pub admin: Signer<'info>,
+    #[account(
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    #[account(
+        init_if_needed,
+        payer = admin,
+        seeds = [b"mint", mint.as_ref()],
+        bump,
+        space = 8 + MintEntry::INIT_SPACE,
+    )]
+    pub mint_entry: Account<'info, MintEntry>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> SetMintStatus<'info> {
+    pub fn set_mint_status(&mut self, mint: Pubkey, status: MintStatus, bumps: &SetMintStatusBumps) -> Result<()> {
+        self.mint_entry.set_inner(MintEntry {
+            mint,
+            status,
+            bump: bumps.mint_entry,
+        });
+
+        Ok(())
+    }
+}
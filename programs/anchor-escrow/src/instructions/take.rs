@@ -1,7 +1,9 @@
-use anchor_lang::prelude::*;
-use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, transfer_checked, CloseAccount, close_account}};
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked}};
 
-use crate::state::Escrow;
+use crate::events::EscrowTaken;
+use crate::state::{bps_of, Config, Escrow};
+use crate::utils::{close_vault_and_escrow, gross_up_for_transfer_fee, is_collection_member, is_native_mint, is_nft, release_from_vault, transfer_checked_with_hook, wrap_sol};
 use crate::EscrowError;
 
 //Create context
@@ -11,34 +13,72 @@ pub struct Take<'info> {
     pub taker: Signer<'info>,
     #[account(mut)]
     pub maker: SystemAccount<'info>,
+    #[account(
+        mut,
+        mint::token_program = token_program_a,
+    )]
     pub mint_a: InterfaceAccount<'info, Mint>,
+    #[account(
+        mint::token_program = token_program_b,
+    )]
     pub mint_b: InterfaceAccount<'info, Mint>,
     #[account(
         init_if_needed,
         payer = taker,
         associated_token::mint = mint_a,
         associated_token::authority = taker,
+        associated_token::token_program = token_program_a,
     )]
     pub taker_ata_a: InterfaceAccount<'info, TokenAccount>,
+    // Both mint B token accounts are omitted when the taker pays in native SOL
     #[account(
         mut,
         associated_token::mint = mint_b,
         associated_token::authority = taker,
+        associated_token::token_program = token_program_b,
     )]
-    pub taker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    pub taker_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         init_if_needed,
         payer = taker,
         associated_token::mint = mint_b,
         associated_token::authority = maker,
+        associated_token::token_program = token_program_b,
     )]
-    pub maker_ata_b: InterfaceAccount<'info, TokenAccount>,
+    pub maker_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: Only receives the protocol fee, pinned to the configured treasury
+    #[account(
+        mut,
+        address = config.treasury,
+    )]
+    pub treasury: UncheckedAccount<'info>,
+    // Only needed when the protocol fee is non-zero and mint B is not native SOL
+    #[account(
+        init_if_needed,
+        payer = taker,
+        associated_token::mint = mint_b,
+        associated_token::authority = treasury,
+        associated_token::token_program = token_program_b,
+    )]
+    pub treasury_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
+    // Integrator's mint B account, paid the referrer fee when one is requested
+    #[account(
+        mut,
+        token::mint = mint_b,
+        token::token_program = token_program_b,
+    )]
+    pub referrer_ata_b: Option<InterfaceAccount<'info, TokenAccount>>,
     #[account(
         mut,
-        close = maker,
         has_one = maker,
         has_one = mint_a,
-        has_one = mint_b,
+        // Collection offers accept any member NFT, checked in the handler
+        constraint = escrow.nft.any_from_collection || escrow.mint_b == mint_b.key() @ ErrorCode::ConstraintHasOne,
         seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
         bump = escrow.bump,
     )]
@@ -47,73 +87,204 @@ pub struct Take<'info> {
         mut,
         associated_token::mint = mint_a,
         associated_token::authority = escrow,
+        associated_token::token_program = token_program_a,
     )]
     pub vault: InterfaceAccount<'info, TokenAccount>,
     pub associated_token_program: Program<'info, AssociatedToken>,
-    pub token_program: Interface<'info, TokenInterface>,
+    pub token_program_a: Interface<'info, TokenInterface>,
+    pub token_program_b: Interface<'info, TokenInterface>,
     pub system_program: Program<'info, System>,
 }
 
+// Mint B fees on a fill
+#[derive(Clone, Copy, Debug, Default)]
+pub struct TakeFees {
+    pub protocol: u64, // Withheld from the maker's proceeds
+    pub referrer: u64, // Paid by the taker on top of the price
+}
+
 //Deposit tokens from taker to maker
-//Transfer tokens from vault to taker
-//Close vault account
+//Transfer the proportional share of the vault to taker
+//Close vault and escrow accounts once the last unit is filled
 impl<'info> Take<'info> {
-    pub fn deposit(&mut self) -> Result<()> {
+    // Fix a Dutch auction's price at the current clock, the fill that follows closes the escrow
+    pub fn apply_auction_price(&mut self) -> Result<()> {
+        if self.escrow.auction.is_none() {
+            return Ok(());
+        }
+
+        let now = self.escrow.now(&Clock::get()?);
+        self.escrow.receive = self.escrow.current_receive(now)?;
+
+        Ok(())
+    }
+
+    // Taker's limit on a full take, including their referrer fee, so an `update` landing first
+    // cannot raise the price
+    pub fn check_max_amount_b(&self, amount_b: u64, referrer_fee_bps: u16, max_amount_b: u64) -> Result<()> {
+        let total = amount_b.checked_add(bps_of(amount_b, referrer_fee_bps)?).ok_or(EscrowError::Overflow)?;
+        require!(total <= max_amount_b, EscrowError::SlippageExceeded);
+
+        Ok(())
+    }
+
+    // Taker's limit on a partial fill, so an `update` landing first cannot shrink what `amount_b` buys
+    pub fn check_min_amount_a(&self, amount_b: u64, min_amount_a: u64) -> Result<()> {
+        // Oversized fills are left for `deposit` to reject
+        require!(
+            amount_b > self.escrow.receive || self.escrow.share_of_deposit(amount_b) >= min_amount_a,
+            EscrowError::SlippageExceeded
+        );
+
+        Ok(())
+    }
+
+    // Returns the fees charged on the fill
+    pub fn deposit(&mut self, amount_b: u64, referrer_fee_bps: u16, remaining_accounts: &[AccountInfo<'info>]) -> Result<TakeFees> {
+        require!(!self.config.paused, EscrowError::Paused);
+
+        require!(
+            self.escrow.can_be_taken_by(self.taker.key),
+            EscrowError::UnauthorizedTaker
+        );
+
         // Check if lock period has elapsed
         let clock = Clock::get()?;
-        let current_slot = clock.slot as i64;
-        let lock_period = self.escrow.lock_period;
+        let now = self.escrow.now(&clock);
 
         require!(
-            current_slot >= self.escrow.start_time + lock_period,
+            now >= self.escrow.lock_ends_at()?,
             EscrowError::EscrowLocked
         );
 
-        let cpi_program = self.token_program.to_account_info();
+        require!(!self.escrow.is_expired(now), EscrowError::EscrowExpired);
 
-        let cpi_accounts = TransferChecked {
-            from: self.taker_ata_b.to_account_info(),
-            to: self.maker_ata_b.to_account_info(),
-            authority: self.taker.to_account_info(),
-            mint: self.mint_b.to_account_info(),
+        if self.escrow.nft.any_from_collection {
+            require!(
+                is_nft(&self.mint_b) && is_collection_member(&self.mint_b, &self.escrow.mint_b)?,
+                EscrowError::NotInCollection
+            );
+        }
+
+        require!(
+            amount_b > 0 && amount_b <= self.escrow.receive,
+            EscrowError::InvalidFillAmount
+        );
+
+        require!(
+            self.escrow.auction.is_none() || amount_b == self.escrow.receive,
+            EscrowError::AuctionNotDivisible
+        );
+
+        require!(
+            referrer_fee_bps <= self.config.max_referrer_fee_bps,
+            EscrowError::ReferrerFeeTooHigh
+        );
+
+        // The protocol fee comes out of the maker's proceeds. The referrer fee is the taker's
+        // own choice, so they pay it on top and can never route it back to themselves at the
+        // maker's expense
+        let fees = TakeFees {
+            protocol: self.config.protocol_fee(amount_b)?,
+            referrer: bps_of(amount_b, referrer_fee_bps)?,
         };
 
-        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts);
+        let maker_amount = amount_b.checked_sub(fees.protocol).ok_or(EscrowError::Overflow)?;
 
-        transfer_checked(cpi_ctx, self.escrow.receive, self.mint_b.decimals)
+        self.pay_mint_b(self.maker_ata_b.as_ref(), self.maker.to_account_info(), maker_amount, remaining_accounts)?;
+
+        if fees.protocol > 0 {
+            self.pay_mint_b(self.treasury_ata_b.as_ref(), self.treasury.to_account_info(), fees.protocol, remaining_accounts)?;
+        }
+
+        if fees.referrer > 0 {
+            let Some(referrer_ata_b) = &self.referrer_ata_b else {
+                return err!(EscrowError::MissingTokenAccount);
+            };
+
+            self.pay_mint_b(Some(referrer_ata_b), referrer_ata_b.to_account_info(), fees.referrer, remaining_accounts)?;
+        }
+
+        Ok(fees)
     }
 
-    pub fn withdraw_and_close_vault(&mut self) -> Result<()> {
-        let signer_seeds: [&[&[u8]]; 1] = [&[
-            b"escrow",
-            self.maker.key.as_ref(),
-            &self.escrow.seed.to_le_bytes()[..],
-            &[self.escrow.bump]
-        ]];
+    // Send mint B from the taker, either as tokens or as plain lamports when mint B is native SOL
+    fn pay_mint_b(&self, to: Option<&InterfaceAccount<'info, TokenAccount>>, to_wallet: AccountInfo<'info>, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let (Some(from), Some(to)) = (&self.taker_ata_b, to) else {
+            require!(is_native_mint(&self.mint_b.key()), EscrowError::MissingTokenAccount);
+
+            // A wSOL recipient is credited by wrapping, a wallet by a plain lamport transfer
+            if let Some(to) = to {
+                return wrap_sol(&self.system_program, self.taker.to_account_info(), &self.token_program_b, to, amount);
+            }
+
+            let cpi_accounts = Transfer {
+                from: self.taker.to_account_info(),
+                to: to_wallet,
+            };
+
+            return transfer(CpiContext::new(self.system_program.to_account_info(), cpi_accounts), amount);
+        };
 
-        let cpi_program = self.token_program.to_account_info();
+        let cpi_program = self.token_program_b.to_account_info();
 
         let cpi_accounts = TransferChecked {
-            from: self.vault.to_account_info(),
-            to: self.taker_ata_a.to_account_info(),
-            authority: self.escrow.to_account_info(),
-            mint: self.mint_a.to_account_info(),
+            from: from.to_account_info(),
+            to: to.to_account_info(),
+            authority: self.taker.to_account_info(),
+            mint: self.mint_b.to_account_info(),
         };
 
-        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
 
-        transfer_checked(cpi_context, self.vault.amount, self.mint_a.decimals)?;
+        // Gross up so the recipient is credited exactly `amount` after any transfer fee
+        let amount = gross_up_for_transfer_fee(&self.mint_b, amount)?;
 
-        let cpi_program = self.token_program.to_account_info();
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_b.decimals)
+    }
 
-        let cpi_accounts = CloseAccount {
-            account: self.vault.to_account_info(),
-            destination: self.maker.to_account_info(),
-            authority: self.escrow.to_account_info(),
-        };
+    pub fn withdraw_and_close_vault(&mut self, amount_b: u64, fees: TakeFees, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        let amount_a = self.escrow.share_of_deposit(amount_b);
+        require!(amount_a > 0, EscrowError::FillTooSmall);
+
+        let is_final_fill = amount_b == self.escrow.receive;
+
+        let withdraw_amount = release_from_vault(
+            self.maker.as_ref(),
+            &self.mint_a,
+            &self.escrow,
+            &self.vault,
+            &self.taker_ata_a,
+            self.taker.to_account_info(),
+            &self.token_program_a,
+            amount_a,
+            is_final_fill,
+            remaining_accounts,
+        )?;
+
+        self.escrow.deposit = self.escrow.deposit.checked_sub(amount_a).ok_or(EscrowError::Overflow)?;
+        self.escrow.receive = self.escrow.receive.checked_sub(amount_b).ok_or(EscrowError::Overflow)?;
+
+        emit!(EscrowTaken {
+            seed: self.escrow.seed,
+            maker: self.maker.key(),
+            taker: self.taker.key(),
+            mint_a: self.mint_a.key(),
+            mint_b: self.mint_b.key(),
+            amount_a: withdraw_amount,
+            amount_b,
+            protocol_fee: fees.protocol,
+            referrer: self.referrer_ata_b.as_ref().map(|referrer_ata_b| referrer_ata_b.owner),
+            referrer_fee: fees.referrer,
+            remaining_receive: self.escrow.receive,
+            slot: Clock::get()?.slot,
+        });
 
-        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+        if !is_final_fill {
+            return Ok(());
+        }
 
-        close_account(cpi_context)
+        close_vault_and_escrow(self.maker.as_ref(), &self.mint_a, &self.escrow, &self.vault, &self.token_program_a)
     }
 }
\ No newline at end of file
@@ -0,0 +1,147 @@
+use anchor_lang::prelude::*;
+use anchor_spl::associated_token::AssociatedToken;
+
+use crate::events::BasketTaken;
+use crate::state::{Basket, Config};
+use crate::utils::{create_ata_if_needed, drain_and_close_vault, gross_up_for_transfer_fee, load_leg_mint, load_leg_vault, transfer_leg};
+use crate::EscrowError;
+
+// Remaining accounts per deposit leg: mint, vault, taker ATA, token program
+pub const TAKE_DEPOSIT_LEG_ACCOUNTS: usize = 4;
+// Remaining accounts per request leg: mint, taker ATA, maker ATA, treasury ATA, token program
+pub const TAKE_REQUEST_LEG_ACCOUNTS: usize = 5;
+
+#[derive(Accounts)]
+pub struct TakeBasket<'info> {
+    #[account(mut)]
+    pub taker: Signer<'info>,
+    #[account(mut)]
+    pub maker: SystemAccount<'info>,
+    #[account(
+        mut,
+        close = maker,
+        has_one = maker,
+        seeds = [b"basket", maker.key().as_ref(), basket.seed.to_le_bytes().as_ref()],
+        bump = basket.bump,
+    )]
+    pub basket: Account<'info, Basket>,
+    #[account(
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+    /// CHECK: Only owns the treasury ATAs, pinned to the configured treasury
+    #[account(
+        address = config.treasury,
+    )]
+    pub treasury: UncheckedAccount<'info>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> TakeBasket<'info> {
+    pub fn pay_requests(&mut self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        require!(!self.config.paused, EscrowError::Paused);
+
+        let deposit_accounts_len = self.basket.deposits.len() * TAKE_DEPOSIT_LEG_ACCOUNTS;
+        require!(
+            remaining_accounts.len() == deposit_accounts_len + self.basket.requests.len() * TAKE_REQUEST_LEG_ACCOUNTS,
+            EscrowError::InvalidBasketAccounts
+        );
+
+        let request_accounts = &remaining_accounts[deposit_accounts_len..];
+
+        for (leg, accounts) in self.basket.requests.iter().zip(request_accounts.chunks(TAKE_REQUEST_LEG_ACCOUNTS)) {
+            let [mint, taker_ata, maker_ata, treasury_ata, token_program] = accounts else {
+                return err!(EscrowError::InvalidBasketAccounts);
+            };
+
+            let (mint, token_program) = load_leg_mint(mint, token_program, &leg.mint)?;
+
+            // The protocol fee comes out of the maker's proceeds, same as a single escrow
+            let protocol_fee = self.config.protocol_fee(leg.amount)?;
+            let maker_amount = leg.amount.checked_sub(protocol_fee).ok_or(EscrowError::Overflow)?;
+
+            create_ata_if_needed(
+                &self.associated_token_program,
+                &self.system_program,
+                self.taker.to_account_info(),
+                maker_ata.clone(),
+                self.maker.to_account_info(),
+                &mint,
+                &token_program,
+            )?;
+
+            let amount = gross_up_for_transfer_fee(&mint, maker_amount)?;
+            transfer_leg(&token_program, &mint, taker_ata.clone(), maker_ata.clone(), self.taker.to_account_info(), amount, &[])?;
+
+            if protocol_fee == 0 {
+                continue;
+            }
+
+            create_ata_if_needed(
+                &self.associated_token_program,
+                &self.system_program,
+                self.taker.to_account_info(),
+                treasury_ata.clone(),
+                self.treasury.to_account_info(),
+                &mint,
+                &token_program,
+            )?;
+
+            let amount = gross_up_for_transfer_fee(&mint, protocol_fee)?;
+            transfer_leg(&token_program, &mint, taker_ata.clone(), treasury_ata.clone(), self.taker.to_account_info(), amount, &[])?;
+        }
+
+        Ok(())
+    }
+
+    pub fn release_deposits(&mut self, remaining_accounts: &'info [AccountInfo<'info>]) -> Result<()> {
+        let signer_seeds: [&[&[u8]]; 1] = [&[
+            b"basket",
+            self.maker.key.as_ref(),
+            &self.basket.seed.to_le_bytes()[..],
+            &[self.basket.bump]
+        ]];
+
+        for (leg, accounts) in self.basket.deposits.iter().zip(remaining_accounts.chunks(TAKE_DEPOSIT_LEG_ACCOUNTS)) {
+            let [mint, vault, taker_ata, token_program] = accounts else {
+                return err!(EscrowError::InvalidBasketAccounts);
+            };
+
+            let (mint, token_program) = load_leg_mint(mint, token_program, &leg.mint)?;
+            let vault = load_leg_vault(vault, &self.basket.key(), &mint, &token_program)?;
+
+            create_ata_if_needed(
+                &self.associated_token_program,
+                &self.system_program,
+                self.taker.to_account_info(),
+                taker_ata.clone(),
+                self.taker.to_account_info(),
+                &mint,
+                &token_program,
+            )?;
+
+            drain_and_close_vault(
+                &token_program,
+                &mint,
+                &vault,
+                taker_ata.clone(),
+                self.basket.to_account_info(),
+                self.maker.to_account_info(),
+                &signer_seeds,
+            )?;
+        }
+
+        emit!(BasketTaken {
+            seed: self.basket.seed,
+            maker: self.maker.key(),
+            taker: self.taker.key(),
+            deposits: self.basket.deposits.clone(),
+            requests: self.basket.requests.clone(),
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+}
@@ -0,0 +1,165 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{associated_token::AssociatedToken, token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked}};
+
+use crate::events::EscrowUpdated;
+use crate::state::Escrow;
+use crate::utils::{is_native_mint, release_from_vault, transfer_checked_with_hook, wrap_sol};
+use crate::EscrowError;
+
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DepositChange {
+    TopUp(u64), // Mint A moved from the maker into the vault
+    Withdraw(u64), // Mint A returned from the vault to the maker
+}
+
+#[derive(Accounts)]
+pub struct Update<'info> {
+    #[account(mut)]
+    pub maker: Signer<'info>,
+    #[account(
+        mint::token_program = token_program,
+    )]
+    pub mint_a: InterfaceAccount<'info, Mint>,
+    // Only needed to move Mint A, and may be omitted for native SOL top ups. A native SOL
+    // withdrawal passes it, possibly not yet created, to unwrap the lamports through
+    #[account(
+        init_if_needed,
+        payer = maker,
+        associated_token::mint = mint_a,
+        associated_token::authority = maker,
+        associated_token::token_program = token_program,
+    )]
+    pub maker_ata_a: Option<InterfaceAccount<'info, TokenAccount>>,
+    #[account(
+        mut,
+        has_one = mint_a,
+        has_one = maker,
+        seeds = [b"escrow", maker.key().as_ref(), escrow.seed.to_le_bytes().as_ref()],
+        bump = escrow.bump,
+    )]
+    pub escrow: Account<'info, Escrow>,
+    #[account(
+        mut,
+        associated_token::mint = mint_a,
+        associated_token::authority = escrow,
+        associated_token::token_program = token_program,
+    )]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub associated_token_program: Program<'info, AssociatedToken>,
+    pub token_program: Interface<'info, TokenInterface>,
+    pub system_program: Program<'info, System>,
+}
+
+impl<'info> Update<'info> {
+    pub fn update_terms(&mut self, receive: Option<u64>, expires_at: Option<i64>) -> Result<()> {
+        if let Some(receive) = receive {
+            require!(receive > 0, EscrowError::ZeroReceive);
+            require!(!self.escrow.nft.any_from_collection || receive == 1, EscrowError::InvalidCollection);
+            if let Some(auction) = self.escrow.auction {
+                require!(receive >= auction.end_receive, EscrowError::InvalidAuction);
+            }
+            // Takers funded during the commit period can rely on the price not rising
+            if receive > self.escrow.receive {
+                let now = self.escrow.now(&Clock::get()?);
+                require!(now >= self.escrow.commit_ends_at()?, EscrowError::ReceiveLocked);
+            }
+            self.escrow.receive = receive;
+        }
+
+        if let Some(expires_at) = expires_at {
+            // Expiry can only move later, takers relying on the current window are never cut short
+            require!(
+                self.escrow.expires_at.is_some_and(|current| expires_at > current),
+                EscrowError::ExpiryNotExtended
+            );
+            self.escrow.expires_at = Some(expires_at);
+        }
+
+        Ok(())
+    }
+
+    pub fn change_deposit(&mut self, change: DepositChange, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        match change {
+            DepositChange::TopUp(amount) => self.top_up(amount, remaining_accounts)?,
+            DepositChange::Withdraw(amount) => self.withdraw(amount, remaining_accounts)?,
+        }
+
+        // Record what the vault actually holds, net of any Token-2022 transfer fee
+        self.vault.reload()?;
+        self.escrow.deposit = self.vault.amount;
+
+        require!(self.escrow.deposit > 0, EscrowError::ZeroDeposit);
+
+        Ok(())
+    }
+
+    pub fn emit_updated(&self) -> Result<()> {
+        emit!(EscrowUpdated {
+            seed: self.escrow.seed,
+            maker: self.maker.key(),
+            deposit: self.escrow.deposit,
+            receive: self.escrow.receive,
+            expires_at: self.escrow.expires_at,
+            slot: Clock::get()?.slot,
+        });
+
+        Ok(())
+    }
+
+    fn top_up(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        require!(amount > 0, EscrowError::ZeroDeposit);
+
+        let Some(maker_ata_a) = &self.maker_ata_a else {
+            require!(is_native_mint(&self.mint_a.key()), EscrowError::MissingTokenAccount);
+
+            return wrap_sol(&self.system_program, self.maker.to_account_info(), &self.token_program, &self.vault, amount);
+        };
+
+        let cpi_program = self.token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: maker_ata_a.to_account_info(),
+            to: self.vault.to_account_info(),
+            authority: self.maker.to_account_info(),
+            mint: self.mint_a.to_account_info(),
+        };
+
+        let cpi_ctx = CpiContext::new(cpi_program, cpi_accounts)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked_with_hook(cpi_ctx, amount, self.mint_a.decimals)
+    }
+
+    fn withdraw(&mut self, amount: u64, remaining_accounts: &[AccountInfo<'info>]) -> Result<()> {
+        // Pulling liquidity is a partial refund, so the commit period applies
+        let clock = Clock::get()?;
+        let now = self.escrow.now(&clock);
+
+        require!(
+            now >= self.escrow.commit_ends_at()?,
+            EscrowError::RefundLocked
+        );
+
+        // Withdrawing everything is a refund, which also closes the escrow
+        require!(amount > 0 && amount < self.escrow.deposit, EscrowError::InvalidWithdrawAmount);
+
+        let Some(maker_ata_a) = &self.maker_ata_a else {
+            return err!(EscrowError::MissingTokenAccount);
+        };
+
+        release_from_vault(
+            self.maker.as_ref(),
+            &self.mint_a,
+            &self.escrow,
+            &self.vault,
+            maker_ata_a,
+            self.maker.to_account_info(),
+            &self.token_program,
+            amount,
+            false,
+            remaining_accounts,
+        )?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,56 @@
+use anchor_lang::prelude::*;
+
+use crate::state::{valid_fee_bps, Config};
+use crate::EscrowError;
+
+#[derive(Accounts)]
+pub struct UpdateConfig<'info> {
+    pub admin: Signer<'info>,
+    #[account(
+        mut,
+        has_one = admin,
+        seeds = [b"config"],
+        bump = config.bump,
+    )]
+    pub config: Account<'info, Config>,
+}
+
+impl<'info> UpdateConfig<'info> {
+    pub fn update_config(&mut self, new_admin: Option<Pubkey>, fee_bps: Option<u16>, max_referrer_fee_bps: Option<u16>, treasury: Option<Pubkey>) -> Result<()> {
+        if let Some(fee_bps) = fee_bps {
+            self.config.fee_bps = fee_bps;
+        }
+
+        if let Some(max_referrer_fee_bps) = max_referrer_fee_bps {
+            self.config.max_referrer_fee_bps = max_referrer_fee_bps;
+        }
+
+        require!(
+            valid_fee_bps(self.config.fee_bps, self.config.max_referrer_fee_bps),
+            EscrowError::InvalidFeeBps
+        );
+
+        if let Some(treasury) = treasury {
+            self.config.treasury = treasury;
+        }
+
+        if let Some(new_admin) = new_admin {
+            self.config.admin = new_admin;
+        }
+
+        Ok(())
+    }
+
+    pub fn set_paused(&mut self, paused: bool) -> Result<()> {
+        self.config.paused = paused;
+
+        Ok(())
+    }
+
+    pub fn set_mint_policy(&mut self, allowlist_only: bool, reject_risky_mints: bool) -> Result<()> {
+        self.config.allowlist_only = allowlist_only;
+        self.config.reject_risky_mints = reject_risky_mints;
+
+        Ok(())
+    }
+}
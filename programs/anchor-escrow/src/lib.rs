@@ -3,11 +3,16 @@
 
 use anchor_lang::prelude::*;
 
-mod state;
+pub mod events;
+pub mod state;
 mod instructions;
-mod tests;
+mod utils;
 
 use instructions::*;
+use state::{BasketLeg, DutchAuction, LockKind, MintStatus, NftTerms};
+
+// Argument of `update`, named by clients building that instruction
+pub use instructions::DepositChange;
 
 declare_id!("FircrADQ2wgGuvpm8qneNCfKM7o5zoHTWnDQxngpTQ3J");
 
@@ -15,18 +20,87 @@ declare_id!("FircrADQ2wgGuvpm8qneNCfKM7o5zoHTWnDQxngpTQ3J");
 pub mod anchor_escrow {
     use super::*;
 
-    pub fn make(ctx: Context<Make>, seed: u64, deposit: u64, receive: u64, lock_period: i64) -> Result<()> {
-        ctx.accounts.init_escrow(seed, receive, lock_period, &ctx.bumps)?;
-        ctx.accounts.deposit(deposit)
+    pub fn initialize_config(ctx: Context<InitializeConfig>, fee_bps: u16, max_referrer_fee_bps: u16, treasury: Pubkey) -> Result<()> {
+        ctx.accounts.initialize_config(fee_bps, max_referrer_fee_bps, treasury, &ctx.bumps)
+    }
+
+    pub fn update_config(ctx: Context<UpdateConfig>, new_admin: Option<Pubkey>, fee_bps: Option<u16>, max_referrer_fee_bps: Option<u16>, treasury: Option<Pubkey>) -> Result<()> {
+        ctx.accounts.update_config(new_admin, fee_bps, max_referrer_fee_bps, treasury)
+    }
+
+    pub fn set_paused(ctx: Context<UpdateConfig>, paused: bool) -> Result<()> {
+        ctx.accounts.set_paused(paused)
+    }
+
+    pub fn set_mint_policy(ctx: Context<UpdateConfig>, allowlist_only: bool, reject_risky_mints: bool) -> Result<()> {
+        ctx.accounts.set_mint_policy(allowlist_only, reject_risky_mints)
+    }
+
+    pub fn set_mint_status(ctx: Context<SetMintStatus>, mint: Pubkey, status: MintStatus) -> Result<()> {
+        ctx.accounts.set_mint_status(mint, status, &ctx.bumps)
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    pub fn make<'info>(ctx: Context<'_, '_, 'info, 'info, Make<'info>>, seed: u64, deposit: u64, receive: u64, lock_kind: LockKind, lock_period: i64, commit_period: i64, expires_at: Option<i64>, taker: Option<Pubkey>, nft: Option<NftTerms>, auction: Option<DutchAuction>) -> Result<()> {
+        ctx.accounts.init_escrow(seed, receive, lock_kind, lock_period, commit_period, expires_at, taker, nft.unwrap_or_default(), auction, &ctx.bumps)?;
+        ctx.accounts.deposit(deposit, ctx.remaining_accounts)
+    }
+
+    pub fn make_basket<'info>(ctx: Context<'_, '_, 'info, 'info, MakeBasket<'info>>, seed: u64, deposits: Vec<BasketLeg>, requests: Vec<BasketLeg>) -> Result<()> {
+        ctx.accounts.make_basket(seed, deposits, requests, ctx.remaining_accounts, &ctx.bumps)
+    }
+
+    pub fn take_basket<'info>(ctx: Context<'_, '_, 'info, 'info, TakeBasket<'info>>) -> Result<()> {
+        ctx.accounts.pay_requests(ctx.remaining_accounts)?;
+        ctx.accounts.release_deposits(ctx.remaining_accounts)
+    }
+
+    pub fn refund_basket<'info>(ctx: Context<'_, '_, 'info, 'info, RefundBasket<'info>>) -> Result<()> {
+        ctx.accounts.refund_and_close_vaults(ctx.remaining_accounts)
+    }
+
+    pub fn refund<'info>(ctx: Context<'_, '_, 'info, 'info, Refund<'info>>) -> Result<()> {
+        ctx.accounts.refund_and_close_vault(ctx.remaining_accounts)
+    }
+
+    pub fn refund_expired<'info>(ctx: Context<'_, '_, 'info, 'info, RefundExpired<'info>>) -> Result<()> {
+        ctx.accounts.refund_and_close_vault(ctx.remaining_accounts)
+    }
+
+    pub fn update<'info>(ctx: Context<'_, '_, 'info, 'info, Update<'info>>, receive: Option<u64>, deposit_change: Option<DepositChange>, expires_at: Option<i64>) -> Result<()> {
+        ctx.accounts.update_terms(receive, expires_at)?;
+        if let Some(change) = deposit_change {
+            ctx.accounts.change_deposit(change, ctx.remaining_accounts)?;
+        }
+        ctx.accounts.emit_updated()
+    }
+
+    pub fn take<'info>(ctx: Context<'_, '_, 'info, 'info, Take<'info>>, max_amount_b: u64, referrer_fee_bps: u16) -> Result<()> {
+        ctx.accounts.apply_auction_price()?;
+        let receive = ctx.accounts.escrow.receive;
+        ctx.accounts.check_max_amount_b(receive, referrer_fee_bps, max_amount_b)?;
+        let fees = ctx.accounts.deposit(receive, referrer_fee_bps, ctx.remaining_accounts)?;
+        ctx.accounts.withdraw_and_close_vault(receive, fees, ctx.remaining_accounts)
+    }
+
+    pub fn take_partial<'info>(ctx: Context<'_, '_, 'info, 'info, Take<'info>>, amount_b: u64, min_amount_a: u64, referrer_fee_bps: u16) -> Result<()> {
+        ctx.accounts.apply_auction_price()?;
+        ctx.accounts.check_min_amount_a(amount_b, min_amount_a)?;
+        let fees = ctx.accounts.deposit(amount_b, referrer_fee_bps, ctx.remaining_accounts)?;
+        ctx.accounts.withdraw_and_close_vault(amount_b, fees, ctx.remaining_accounts)
+    }
+
+    pub fn bid<'info>(ctx: Context<'_, '_, 'info, 'info, PlaceBid<'info>>, amount_a: u64, amount_b: u64) -> Result<()> {
+        ctx.accounts.place_bid(amount_a, amount_b, ctx.remaining_accounts, &ctx.bumps)
     }
 
-    pub fn refund(ctx: Context<Refund>) -> Result<()> {
-        ctx.accounts.refund_and_close_vault()
+    pub fn accept_bid<'info>(ctx: Context<'_, '_, 'info, 'info, AcceptBid<'info>>) -> Result<()> {
+        let fee = ctx.accounts.settle_bid(ctx.remaining_accounts)?;
+        ctx.accounts.release_deposit(fee, ctx.remaining_accounts)
     }
 
-    pub fn take(ctx: Context<Take>) -> Result<()> {
-        ctx.accounts.deposit()?;
-        ctx.accounts.withdraw_and_close_vault()
+    pub fn cancel_bid<'info>(ctx: Context<'_, '_, 'info, 'info, CancelBid<'info>>) -> Result<()> {
+        ctx.accounts.refund_and_close_bid_vault(ctx.remaining_accounts)
     }
 }
 
@@ -34,4 +108,68 @@ pub mod anchor_escrow {
 pub enum EscrowError {
     #[msg("Escrow is still locked. Lock period has not elapsed yet.")]
     EscrowLocked,
+    #[msg("Fill amount must be greater than zero and at most the remaining receive amount.")]
+    InvalidFillAmount,
+    #[msg("Fill amount is too small to release any of the deposited tokens.")]
+    FillTooSmall,
+    #[msg("Escrow has expired and can no longer be taken.")]
+    EscrowExpired,
+    #[msg("Escrow has not expired yet.")]
+    EscrowNotExpired,
+    #[msg("Signer is not the designated taker for this escrow.")]
+    UnauthorizedTaker,
+    #[msg("Escrow is still committed. Commit period has not elapsed yet.")]
+    RefundLocked,
+    #[msg("Token account is required unless that side of the trade is native SOL.")]
+    MissingTokenAccount,
+    #[msg("Deposit amount must be greater than zero.")]
+    ZeroDeposit,
+    #[msg("Receive amount must be greater than zero.")]
+    ZeroReceive,
+    #[msg("Lock and commit periods cannot be negative.")]
+    NegativeLock,
+    #[msg("Mint A and Mint B must be different.")]
+    SameMint,
+    #[msg("Arithmetic overflow.")]
+    Overflow,
+    #[msg("Expiry can only be extended, and only on escrows that already expire.")]
+    ExpiryNotExtended,
+    #[msg("Withdraw amount must be greater than zero and leave part of the deposit in the vault.")]
+    InvalidWithdrawAmount,
+    #[msg("Protocol and referrer fees cannot exceed 10000 basis points combined.")]
+    InvalidFeeBps,
+    #[msg("Referrer fee exceeds the configured maximum.")]
+    ReferrerFeeTooHigh,
+    #[msg("Program is paused. Only refunds are allowed.")]
+    Paused,
+    #[msg("Mint is not allowed by the mint registry.")]
+    MintNotAllowed,
+    #[msg("Mint has a freeze authority that could freeze the vault.")]
+    FreezableMint,
+    #[msg("Mint has a Token-2022 extension that could claw back or lock escrowed tokens.")]
+    RiskyMintExtension,
+    #[msg("Basket must have between 1 and 4 deposit and request legs.")]
+    InvalidBasketSize,
+    #[msg("Remaining accounts do not match the basket legs.")]
+    InvalidBasketAccounts,
+    #[msg("Mint A must be a single 0-decimal token with no mint authority.")]
+    NotAnNft,
+    #[msg("Mint B must be a Token-2022 collection and exactly one member must be requested.")]
+    InvalidCollection,
+    #[msg("Mint B is not a member of the requested collection.")]
+    NotInCollection,
+    #[msg("Dutch auction needs a positive duration and an end price between one and the start price.")]
+    InvalidAuction,
+    #[msg("Dutch auctions can only be taken whole.")]
+    AuctionNotDivisible,
+    #[msg("Receive can only be lowered until the commit period has elapsed.")]
+    ReceiveLocked,
+    #[msg("Fill is priced beyond the taker's limit.")]
+    SlippageExceeded,
+    #[msg("Only the program's upgrade authority can initialize the config.")]
+    NotUpgradeAuthority,
+    #[msg("Basket legs cannot use mints with a transfer hook.")]
+    TransferHookNotSupported,
+    #[msg("Expiry must be after the lock period ends.")]
+    InvalidExpiry,
 }
\ No newline at end of file
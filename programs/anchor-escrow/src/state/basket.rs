@@ -0,0 +1,23 @@
+use anchor_lang::prelude::*;
+
+pub const MAX_BASKET_LEGS: usize = 4;
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BasketLeg {
+    pub mint: Pubkey,
+    pub amount: u64,
+}
+
+// Multi-asset offer: every deposit leg sits in its own vault and every request leg
+// must be paid in the same take, so the whole bundle settles atomically
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Basket {
+    pub seed: u64,
+    pub maker: Pubkey,
+    pub bump: u8,
+    #[max_len(MAX_BASKET_LEGS)]
+    pub deposits: Vec<BasketLeg>, // Amounts actually received by each vault
+    #[max_len(MAX_BASKET_LEGS)]
+    pub requests: Vec<BasketLeg>, // Amounts owed to the maker per mint
+}
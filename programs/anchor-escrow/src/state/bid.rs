@@ -0,0 +1,14 @@
+use anchor_lang::prelude::*;
+
+// Taker-side counter-offer against an escrow, with mint B already locked in its own vault.
+// One open bid per bidder per escrow, seeded by both addresses
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Bid {
+    pub escrow: Pubkey,
+    pub bidder: Pubkey,
+    pub mint_b: Pubkey,
+    pub amount_a: u64, // Mint A the bidder wants out of the escrow vault
+    pub amount_b: u64, // Mint B actually received by the bid vault
+    pub bump: u8,
+}
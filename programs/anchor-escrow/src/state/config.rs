@@ -0,0 +1,35 @@
+use anchor_lang::prelude::*;
+
+use crate::EscrowError;
+
+pub const MAX_BPS: u16 = 10_000;
+
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Config {
+    pub admin: Pubkey, // Only signer allowed to change the config
+    pub treasury: Pubkey, // Wallet whose mint B accounts collect the protocol fee
+    pub fee_bps: u16, // Protocol fee taken out of every mint B payment
+    pub max_referrer_fee_bps: u16, // Cap on the referrer fee a taker may pay an integrator on top of the price
+    pub paused: bool, // Emergency stop for make and take, refunds stay open
+    pub allowlist_only: bool, // Make only accepts mints the admin registered as allowed
+    pub reject_risky_mints: bool, // Make rejects unregistered freezable mints and risky Token-2022 extensions
+    pub bump: u8,
+}
+
+impl Config {
+    // Protocol fee owed on a mint B payment, rounded down in the payer's favour
+    pub fn protocol_fee(&self, amount: u64) -> Result<u64> {
+        bps_of(amount, self.fee_bps)
+    }
+}
+
+// Protocol fee plus the largest referrer fee can never exceed the payment itself
+pub fn valid_fee_bps(fee_bps: u16, max_referrer_fee_bps: u16) -> bool {
+    fee_bps as u32 + max_referrer_fee_bps as u32 <= MAX_BPS as u32
+}
+
+pub fn bps_of(amount: u64, bps: u16) -> Result<u64> {
+    let fee = amount as u128 * bps as u128 / MAX_BPS as u128;
+    Ok(u64::try_from(fee).map_err(|_| EscrowError::Overflow)?)
+}
@@ -1,5 +1,27 @@
 use anchor_lang::prelude::*;
 
+use crate::EscrowError;
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LockKind {
+    Slot, // Lock, commit and expiry values are slots
+    UnixTimestamp, // Lock, commit and expiry values are seconds
+}
+
+// Optional NFT mode, both flags off for plain fungible escrows
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct NftTerms {
+    pub deposit_is_nft: bool, // Mint A must be a single, unmintable 0-decimal token
+    pub any_from_collection: bool, // Mint B is a Token-2022 collection and any one of its member NFTs fills the escrow
+}
+
+// Descending price, from the escrow's `receive` down to `end_receive`
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DutchAuction {
+    pub end_receive: u64, // Floor price in mint B, reached once the duration has passed
+    pub duration: i64, // Slots or seconds over which the price decays, counted from the end of the lock period
+}
+
 #[account]
 #[derive(InitSpace, Debug)]
 pub struct Escrow {
@@ -7,8 +29,81 @@ pub struct Escrow {
     pub maker: Pubkey,
     pub mint_a: Pubkey,
     pub mint_b: Pubkey,
-    pub receive: u64,
+    pub deposit: u64, // Mint A still held in the vault, reduced by partial fills
+    pub receive: u64, // Mint B still owed to the maker, reduced by partial fills
     pub bump: u8,
-    pub start_time: i64, // Slot when escrow was created
-    pub lock_period: i64, // Slots that must pass before escrow can be taken
-}
\ No newline at end of file
+    pub lock_kind: LockKind, // Whether the timing fields below are slots or seconds
+    pub start_time: i64, // Slot or unix timestamp when escrow was created
+    pub lock_period: i64, // Slots or seconds that must pass before escrow can be taken
+    pub commit_period: i64, // Slots or seconds that must pass before the maker can refund
+    pub expires_at: Option<i64>, // Slot or unix timestamp after which escrow can no longer be taken
+    pub taker: Option<Pubkey>, // Only this signer may take the escrow, if set
+    pub nft: NftTerms,
+    pub auction: Option<DutchAuction>, // Dutch auctions are only ever taken whole
+}
+
+impl LockKind {
+    pub fn now(&self, clock: &Clock) -> i64 {
+        match self {
+            LockKind::Slot => clock.slot as i64,
+            LockKind::UnixTimestamp => clock.unix_timestamp,
+        }
+    }
+}
+
+impl Escrow {
+    pub fn now(&self, clock: &Clock) -> i64 {
+        self.lock_kind.now(clock)
+    }
+
+    // Slot or timestamp from which the escrow can be taken
+    pub fn lock_ends_at(&self) -> Result<i64> {
+        Ok(self.start_time.checked_add(self.lock_period).ok_or(EscrowError::Overflow)?)
+    }
+
+    // Slot or timestamp from which the maker can refund
+    pub fn commit_ends_at(&self) -> Result<i64> {
+        Ok(self.start_time.checked_add(self.commit_period).ok_or(EscrowError::Overflow)?)
+    }
+
+    pub fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| now >= expires_at)
+    }
+
+    pub fn can_be_taken_by(&self, taker: &Pubkey) -> bool {
+        self.taker.is_none() || self.taker == Some(*taker)
+    }
+
+    // Mint B required right now, rounded in the maker's favour while the price decays linearly
+    pub fn current_receive(&self, now: i64) -> Result<u64> {
+        let Some(auction) = self.auction else {
+            return Ok(self.receive);
+        };
+
+        let elapsed = now.saturating_sub(self.lock_ends_at()?).clamp(0, auction.duration);
+        let price_range = self.receive.checked_sub(auction.end_receive).ok_or(EscrowError::Overflow)?;
+        let decay = price_range as u128 * elapsed as u128 / auction.duration as u128;
+
+        Ok(self.receive - decay as u64)
+    }
+
+    // Mint A released to a taker paying `amount_b` of the remaining `receive`.
+    // Rounds down so the maker is never short-changed on partial fills.
+    pub fn share_of_deposit(&self, amount_b: u64) -> u64 {
+        if amount_b == self.receive {
+            return self.deposit;
+        }
+
+        (self.deposit as u128 * amount_b as u128 / self.receive as u128) as u64
+    }
+
+    // Mint B no longer owed once `amount_a` of the deposit is sold through a bid.
+    // Rounds down so the remaining terms never get cheaper for later takers
+    pub fn share_of_receive(&self, amount_a: u64) -> u64 {
+        if amount_a == self.deposit {
+            return self.receive;
+        }
+
+        (self.receive as u128 * amount_a as u128 / self.deposit as u128) as u64
+    }
+}
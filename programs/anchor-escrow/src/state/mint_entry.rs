@@ -0,0 +1,16 @@
+use anchor_lang::prelude::*;
+
+#[derive(AnchorSerialize, AnchorDeserialize, InitSpace, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MintStatus {
+    Allowed, // Accepted by make even under the allowlist and risky mint policies
+    Denied, // Always rejected by make
+}
+
+// Admin registry entry for a single mint, seeded by the mint address
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct MintEntry {
+    pub mint: Pubkey,
+    pub status: MintStatus,
+    pub bump: u8,
+}
@@ -1,3 +1,11 @@
+pub mod basket;
+pub mod bid;
+pub mod config;
 pub mod escrow;
+pub mod mint_entry;
 
-pub use escrow::*;
\ No newline at end of file
+pub use basket::*;
+pub use bid::*;
+pub use config::*;
+pub use escrow::*;
+pub use mint_entry::*;
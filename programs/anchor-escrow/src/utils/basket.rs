@@ -0,0 +1,148 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    associated_token::{create_idempotent, get_associated_token_address_with_program_id, AssociatedToken, Create},
+    token_2022::spl_token_2022::extension::transfer_hook::TransferHook,
+    token_interface::{close_account, get_mint_extension_data, transfer_checked, CloseAccount, Mint, Token2022, TokenAccount, TokenInterface, TransferChecked},
+};
+
+use crate::state::{BasketLeg, Config, MAX_BASKET_LEGS};
+use crate::utils::{check_mint_policy, harvest_withheld_fees};
+use crate::EscrowError;
+
+// Leg counts within bounds and every mint used at most once across the whole basket
+pub fn validate_basket_legs(deposits: &[BasketLeg], requests: &[BasketLeg]) -> Result<()> {
+    require!(
+        (1..=MAX_BASKET_LEGS).contains(&deposits.len()) && (1..=MAX_BASKET_LEGS).contains(&requests.len()),
+        EscrowError::InvalidBasketSize
+    );
+
+    require!(deposits.iter().all(|leg| leg.amount > 0), EscrowError::ZeroDeposit);
+    require!(requests.iter().all(|leg| leg.amount > 0), EscrowError::ZeroReceive);
+
+    let mints: Vec<&Pubkey> = deposits.iter().chain(requests).map(|leg| &leg.mint).collect();
+    for (i, mint) in mints.iter().enumerate() {
+        require!(!mints[..i].contains(mint), EscrowError::SameMint);
+    }
+
+    Ok(())
+}
+
+// Mint and token program of one leg, checked against each other and the mint recorded for the leg
+pub fn load_leg_mint<'info>(
+    mint: &'info AccountInfo<'info>,
+    token_program: &'info AccountInfo<'info>,
+    expected_mint: &Pubkey,
+) -> Result<(InterfaceAccount<'info, Mint>, Interface<'info, TokenInterface>)> {
+    require_keys_eq!(mint.key(), *expected_mint, EscrowError::InvalidBasketAccounts);
+
+    let token_program = Interface::<TokenInterface>::try_from(token_program)?;
+    require_keys_eq!(*mint.owner, token_program.key(), EscrowError::InvalidBasketAccounts);
+
+    Ok((InterfaceAccount::try_from(mint)?, token_program))
+}
+
+// The vault of a basket leg is always the basket's ATA for that mint
+pub fn load_leg_vault<'info>(
+    vault: &'info AccountInfo<'info>,
+    basket: &Pubkey,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<InterfaceAccount<'info, TokenAccount>> {
+    let expected = get_associated_token_address_with_program_id(basket, &mint.key(), &token_program.key());
+    require_keys_eq!(vault.key(), expected, EscrowError::InvalidBasketAccounts);
+
+    InterfaceAccount::try_from(vault)
+}
+
+// Basket legs have a fixed set of accounts with no room for a hook's extra accounts, so a hooked
+// mint could never be paid out. Rejected even with no hook program set yet, since its authority can
+// add one after the basket is made
+pub fn require_no_transfer_hook(mint: &InterfaceAccount<Mint>) -> Result<()> {
+    let mint_info = mint.to_account_info();
+
+    if *mint_info.owner != Token2022::id() {
+        return Ok(());
+    }
+
+    require!(
+        get_mint_extension_data::<TransferHook>(&mint_info).is_err(),
+        EscrowError::TransferHookNotSupported
+    );
+
+    Ok(())
+}
+
+// The associated token program rejects any address that is not the owner's ATA
+#[allow(clippy::too_many_arguments)]
+pub fn create_ata_if_needed<'info>(
+    associated_token_program: &Program<'info, AssociatedToken>,
+    system_program: &Program<'info, System>,
+    payer: AccountInfo<'info>,
+    ata: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    mint: &InterfaceAccount<'info, Mint>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    let cpi_accounts = Create {
+        payer,
+        associated_token: ata,
+        authority,
+        mint: mint.to_account_info(),
+        system_program: system_program.to_account_info(),
+        token_program: token_program.to_account_info(),
+    };
+
+    create_idempotent(CpiContext::new(associated_token_program.to_account_info(), cpi_accounts))
+}
+
+pub fn transfer_leg<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    from: AccountInfo<'info>,
+    to: AccountInfo<'info>,
+    authority: AccountInfo<'info>,
+    amount: u64,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    let cpi_accounts = TransferChecked {
+        from,
+        to,
+        authority,
+        mint: mint.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds);
+
+    transfer_checked(cpi_ctx, amount, mint.decimals)
+}
+
+// Empty a basket vault into `to` and return its rent to the maker
+pub fn drain_and_close_vault<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    to: AccountInfo<'info>,
+    basket: AccountInfo<'info>,
+    maker: AccountInfo<'info>,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    transfer_leg(token_program, mint, vault.to_account_info(), to, basket.clone(), vault.amount, signer_seeds)?;
+
+    harvest_withheld_fees(token_program, mint, vault)?;
+
+    let cpi_accounts = CloseAccount {
+        account: vault.to_account_info(),
+        destination: maker,
+        authority: basket,
+    };
+
+    close_account(CpiContext::new_with_signer(token_program.to_account_info(), cpi_accounts, signer_seeds))
+}
+
+// Registry entries are not part of the fixed accounts, so the PDA is checked here before applying the policy
+pub fn check_leg_mint_policy(config: &Config, mint: &InterfaceAccount<Mint>, entry: &AccountInfo) -> Result<()> {
+    let (expected, _) = Pubkey::find_program_address(&[b"mint", mint.key().as_ref()], &crate::ID);
+    require_keys_eq!(entry.key(), expected, EscrowError::InvalidBasketAccounts);
+
+    check_mint_policy(config, mint, entry)
+}
@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        self,
+        extension::{default_account_state::DefaultAccountState, BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+        state::AccountState,
+    },
+    token_interface::{Mint, Token2022},
+};
+
+use crate::state::{Config, MintEntry, MintStatus};
+use crate::EscrowError;
+
+// Registry status of a mint, None when the admin never registered it
+pub fn mint_status(entry: &AccountInfo) -> Result<Option<MintStatus>> {
+    if *entry.owner != crate::ID || entry.data_is_empty() {
+        return Ok(None);
+    }
+
+    let entry = MintEntry::try_deserialize(&mut &entry.try_borrow_data()?[..])?;
+
+    Ok(Some(entry.status))
+}
+
+pub fn check_mint_policy(config: &Config, mint: &InterfaceAccount<Mint>, entry: &AccountInfo) -> Result<()> {
+    match mint_status(entry)? {
+        Some(MintStatus::Denied) => return err!(EscrowError::MintNotAllowed),
+        // An explicit approval overrides the automatic checks, e.g. for freezable stablecoins
+        Some(MintStatus::Allowed) => return Ok(()),
+        None => require!(!config.allowlist_only, EscrowError::MintNotAllowed),
+    }
+
+    if !config.reject_risky_mints {
+        return Ok(());
+    }
+
+    require!(!has_risky_extension(mint)?, EscrowError::RiskyMintExtension);
+    require!(mint.freeze_authority.is_none(), EscrowError::FreezableMint);
+
+    Ok(())
+}
+
+// Extensions that let someone other than the vault owner move, lock or strand escrowed tokens
+fn has_risky_extension(mint: &InterfaceAccount<Mint>) -> Result<bool> {
+    let mint_info = mint.to_account_info();
+
+    if *mint_info.owner != Token2022::id() {
+        return Ok(false);
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+
+    for extension in state.get_extension_types()? {
+        match extension {
+            ExtensionType::PermanentDelegate | ExtensionType::NonTransferable => return Ok(true),
+            ExtensionType::DefaultAccountState => {
+                let default_state = state.get_extension::<DefaultAccountState>()?;
+                if default_state.state == AccountState::Frozen as u8 {
+                    return Ok(true);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(false)
+}
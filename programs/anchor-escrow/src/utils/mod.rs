@@ -0,0 +1,17 @@
+pub mod basket;
+pub mod mint_policy;
+pub mod native_sol;
+pub mod nft;
+pub mod refund;
+pub mod release;
+pub mod transfer_fee;
+pub mod transfer_hook;
+
+pub use basket::*;
+pub use mint_policy::*;
+pub use native_sol::*;
+pub use nft::*;
+pub use refund::*;
+pub use release::*;
+pub use transfer_fee::*;
+pub use transfer_hook::*;
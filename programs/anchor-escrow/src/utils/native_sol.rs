@@ -0,0 +1,32 @@
+use anchor_lang::{prelude::*, system_program::{transfer, Transfer}};
+use anchor_spl::{
+    token::spl_token,
+    token_2022::spl_token_2022,
+    token_interface::{sync_native, SyncNative, TokenAccount, TokenInterface},
+};
+
+pub fn is_native_mint(mint: &Pubkey) -> bool {
+    *mint == spl_token::native_mint::ID || *mint == spl_token_2022::native_mint::ID
+}
+
+// Pay lamports straight into a wSOL token account and sync its token balance
+pub fn wrap_sol<'info>(
+    system_program: &Program<'info, System>,
+    from: AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    amount: u64,
+) -> Result<()> {
+    let cpi_accounts = Transfer {
+        from,
+        to: to.to_account_info(),
+    };
+
+    transfer(CpiContext::new(system_program.to_account_info(), cpi_accounts), amount)?;
+
+    let cpi_accounts = SyncNative {
+        account: to.to_account_info(),
+    };
+
+    sync_native(CpiContext::new(token_program.to_account_info(), cpi_accounts))
+}
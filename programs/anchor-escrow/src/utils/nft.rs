@@ -0,0 +1,42 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::spl_token_2022::{self, extension::{BaseStateWithExtensions, StateWithExtensions}},
+    token_interface::{Mint, Token2022},
+};
+use spl_token_group_interface::state::{TokenGroup, TokenGroupMember};
+
+// A single token that can never be minted again
+pub fn is_nft(mint: &InterfaceAccount<Mint>) -> bool {
+    mint.decimals == 0 && mint.supply == 1 && mint.mint_authority.is_none()
+}
+
+// Token-2022 only lets the mint authority attach group data to the mint itself,
+// so the extension being present is enough to trust it
+pub fn is_collection(mint: &InterfaceAccount<Mint>) -> Result<bool> {
+    let mint_info = mint.to_account_info();
+
+    if *mint_info.owner != Token2022::id() {
+        return Ok(false);
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+
+    Ok(state.get_extension::<TokenGroup>().is_ok_and(|group| group.mint == mint.key()))
+}
+
+// Member data also needs the group's update authority to sign, so it cannot be forged
+pub fn is_collection_member(mint: &InterfaceAccount<Mint>, collection: &Pubkey) -> Result<bool> {
+    let mint_info = mint.to_account_info();
+
+    if *mint_info.owner != Token2022::id() {
+        return Ok(false);
+    }
+
+    let data = mint_info.try_borrow_data()?;
+    let state = StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)?;
+
+    Ok(state
+        .get_extension::<TokenGroupMember>()
+        .is_ok_and(|member| member.mint == mint.key() && member.group == *collection))
+}
@@ -0,0 +1,71 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, CloseAccount, close_account};
+
+use crate::events::EscrowRefunded;
+use crate::state::Escrow;
+use crate::utils::{harvest_withheld_fees, transfer_checked_with_hook};
+use crate::EscrowError;
+
+// Send the whole vault back to the maker and close it. Shared by `refund` and `refund_expired`,
+// which only differ in when they are allowed; the escrow itself is closed by their constraints.
+pub fn return_vault_to_maker<'info>(
+    maker: AccountInfo<'info>,
+    mint_a: &InterfaceAccount<'info, Mint>,
+    maker_ata_a: Option<&InterfaceAccount<'info, TokenAccount>>,
+    escrow: &Account<'info, Escrow>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<()> {
+    let signer_seeds: [&[&[u8]]; 1] = [&[
+        b"escrow",
+        maker.key.as_ref(),
+        &escrow.seed.to_le_bytes()[..],
+        &[escrow.bump]
+    ]];
+
+    emit!(EscrowRefunded {
+        seed: escrow.seed,
+        maker: maker.key(),
+        mint_a: mint_a.key(),
+        mint_b: escrow.mint_b,
+        amount: vault.amount,
+        slot: Clock::get()?.slot,
+    });
+
+    // A native SOL vault is closed straight to the maker, which returns the
+    // wrapped lamports along with the rent
+    if !vault.is_native() {
+        let Some(maker_ata_a) = maker_ata_a else {
+            return err!(EscrowError::MissingTokenAccount);
+        };
+
+        let cpi_program = token_program.to_account_info();
+
+        let cpi_accounts = TransferChecked {
+            from: vault.to_account_info(),
+            to: maker_ata_a.to_account_info(),
+            mint: mint_a.to_account_info(),
+            authority: escrow.to_account_info(),
+        };
+
+        let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds)
+            .with_remaining_accounts(remaining_accounts.to_vec());
+
+        transfer_checked_with_hook(cpi_context, vault.amount, mint_a.decimals)?;
+
+        harvest_withheld_fees(token_program, mint_a, vault)?;
+    }
+
+    let cpi_program = token_program.to_account_info();
+
+    let cpi_accounts = CloseAccount {
+        account: vault.to_account_info(),
+        destination: maker,
+        authority: escrow.to_account_info(),
+    };
+
+    let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+    close_account(cpi_context)
+}
@@ -0,0 +1,93 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface, TransferChecked, CloseAccount, close_account};
+
+use crate::state::Escrow;
+use crate::utils::{harvest_withheld_fees, transfer_checked_with_hook};
+
+// Send `amount_a` of the deposit out of the vault, or the whole vault on the final fill, and
+// return what was sent. Shared by `take`, `accept_bid` and `update` withdrawals.
+// A native SOL deposit is unwrapped by closing `to` back to `to_owner`, who must have signed.
+#[allow(clippy::too_many_arguments)]
+pub fn release_from_vault<'info>(
+    maker: &AccountInfo<'info>,
+    mint_a: &InterfaceAccount<'info, Mint>,
+    escrow: &Account<'info, Escrow>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    to: &InterfaceAccount<'info, TokenAccount>,
+    to_owner: AccountInfo<'info>,
+    token_program: &Interface<'info, TokenInterface>,
+    amount_a: u64,
+    is_final_fill: bool,
+    remaining_accounts: &[AccountInfo<'info>],
+) -> Result<u64> {
+    let signer_seeds: [&[&[u8]]; 1] = [&[
+        b"escrow",
+        maker.key.as_ref(),
+        &escrow.seed.to_le_bytes()[..],
+        &[escrow.bump]
+    ]];
+
+    let cpi_program = token_program.to_account_info();
+
+    let cpi_accounts = TransferChecked {
+        from: vault.to_account_info(),
+        to: to.to_account_info(),
+        authority: escrow.to_account_info(),
+        mint: mint_a.to_account_info(),
+    };
+
+    let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds)
+        .with_remaining_accounts(remaining_accounts.to_vec());
+
+    // The final fill sweeps the whole vault so it can be closed
+    let withdraw_amount = if is_final_fill { vault.amount } else { amount_a };
+
+    transfer_checked_with_hook(cpi_context, withdraw_amount, mint_a.decimals)?;
+
+    if vault.is_native() {
+        // Closing the wSOL account pays it out to its owner as lamports
+        let cpi_program = token_program.to_account_info();
+
+        let cpi_accounts = CloseAccount {
+            account: to.to_account_info(),
+            destination: to_owner.clone(),
+            authority: to_owner,
+        };
+
+        close_account(CpiContext::new(cpi_program, cpi_accounts))?;
+    }
+
+    Ok(withdraw_amount)
+}
+
+// Once the whole deposit is sold, close the emptied vault and then the escrow to the maker
+pub fn close_vault_and_escrow<'info>(
+    maker: &AccountInfo<'info>,
+    mint_a: &InterfaceAccount<'info, Mint>,
+    escrow: &Account<'info, Escrow>,
+    vault: &InterfaceAccount<'info, TokenAccount>,
+    token_program: &Interface<'info, TokenInterface>,
+) -> Result<()> {
+    let signer_seeds: [&[&[u8]]; 1] = [&[
+        b"escrow",
+        maker.key.as_ref(),
+        &escrow.seed.to_le_bytes()[..],
+        &[escrow.bump]
+    ]];
+
+    harvest_withheld_fees(token_program, mint_a, vault)?;
+
+    let cpi_program = token_program.to_account_info();
+
+    let cpi_accounts = CloseAccount {
+        account: vault.to_account_info(),
+        destination: maker.clone(),
+        authority: escrow.to_account_info(),
+    };
+
+    let cpi_context = CpiContext::new_with_signer(cpi_program, cpi_accounts, &signer_seeds);
+
+    close_account(cpi_context)?;
+
+    escrow.close(maker.clone())
+}
@@ -0,0 +1,53 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::spl_token_2022::extension::transfer_fee::TransferFeeConfig,
+    token_2022_extensions::{harvest_withheld_tokens_to_mint, HarvestWithheldTokensToMint},
+    token_interface::{get_mint_extension_data, Mint, TokenAccount, TokenInterface, Token2022},
+};
+
+use crate::EscrowError;
+
+// Transfer fee config of a Token-2022 mint, None for legacy mints or mints without the extension
+pub fn transfer_fee_config(mint: &InterfaceAccount<Mint>) -> Option<TransferFeeConfig> {
+    let mint_info = mint.to_account_info();
+
+    if *mint_info.owner != Token2022::id() {
+        return None;
+    }
+
+    get_mint_extension_data::<TransferFeeConfig>(&mint_info).ok()
+}
+
+// Amount to send so the recipient is credited exactly `net` after the transfer fee is withheld
+pub fn gross_up_for_transfer_fee(mint: &InterfaceAccount<Mint>, net: u64) -> Result<u64> {
+    let Some(config) = transfer_fee_config(mint) else {
+        return Ok(net);
+    };
+
+    let epoch = Clock::get()?.epoch;
+    let fee = config
+        .calculate_inverse_epoch_fee(epoch, net)
+        .ok_or(EscrowError::Overflow)?;
+
+    Ok(net.checked_add(fee).ok_or(EscrowError::Overflow)?)
+}
+
+// Token-2022 refuses to close an account holding withheld fees, so sweep them to the mint first
+pub fn harvest_withheld_fees<'info>(
+    token_program: &Interface<'info, TokenInterface>,
+    mint: &InterfaceAccount<'info, Mint>,
+    account: &InterfaceAccount<'info, TokenAccount>,
+) -> Result<()> {
+    if transfer_fee_config(mint).is_none() {
+        return Ok(());
+    }
+
+    let cpi_accounts = HarvestWithheldTokensToMint {
+        token_program_id: token_program.to_account_info(),
+        mint: mint.to_account_info(),
+    };
+
+    let cpi_ctx = CpiContext::new(token_program.to_account_info(), cpi_accounts);
+
+    harvest_withheld_tokens_to_mint(cpi_ctx, vec![account.to_account_info()])
+}
@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+use anchor_spl::{
+    token_2022::spl_token_2022::onchain::invoke_transfer_checked,
+    token_interface::TransferChecked,
+};
+
+// Drop-in for `transfer_checked` that also works for Token-2022 mints with a transfer hook.
+// The hook's extra accounts (plus its program and validation PDA) are looked up in the
+// context's remaining accounts; mints without a hook ignore them.
+pub fn transfer_checked_with_hook<'info>(
+    ctx: CpiContext<'_, '_, '_, 'info, TransferChecked<'info>>,
+    amount: u64,
+    decimals: u8,
+) -> Result<()> {
+    invoke_transfer_checked(
+        ctx.program.key,
+        ctx.accounts.from,
+        ctx.accounts.mint,
+        ctx.accounts.to,
+        ctx.accounts.authority,
+        &ctx.remaining_accounts,
+        amount,
+        decimals,
+        ctx.signer_seeds,
+    )
+    .map_err(Into::into)
+}
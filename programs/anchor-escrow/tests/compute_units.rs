@@ -0,0 +1,161 @@
+// Compute-unit benchmarks: runs each instruction variant once, writes a markdown and JSON report
+// and fails when any variant exceeds its measured baseline in `compute_units.json` by more than
+// the headroom stated there.
+//
+// A variant with no baseline yet has this run's measurement recorded as its baseline, so the
+// first run after `anchor build` fills in `compute_units.json` to be committed. Set
+// ESCROW_CU_RECORD=1 to replace every baseline with this run's measurements instead. Set
+// ESCROW_CU_BASELINES to use another baselines file, and ESCROW_CU_REPORT_DIR to write the
+// report somewhere other than `target/compute-units`.
+
+use {
+    litesvm::types::TransactionResult,
+    litesvm_token::CreateAssociatedTokenAccount,
+    serde::{Deserialize, Serialize},
+    solana_signer::Signer,
+    std::{collections::BTreeMap, fs, path::PathBuf},
+};
+
+use anchor_escrow_harness::EscrowTestEnv;
+
+const BASELINES_ENV: &str = "ESCROW_CU_BASELINES";
+const RECORD_ENV: &str = "ESCROW_CU_RECORD";
+const REPORT_DIR_ENV: &str = "ESCROW_CU_REPORT_DIR";
+
+#[derive(Serialize, Deserialize)]
+struct Baselines {
+    headroom_percent: u64, // Growth over a baseline tolerated before the benchmark fails
+    compute_units: BTreeMap<String, u64>, // Measured per instruction variant
+}
+
+impl Baselines {
+    fn threshold(&self, instruction: &str) -> u64 {
+        let baseline = self.compute_units[instruction];
+
+        baseline + baseline * self.headroom_percent / 100
+    }
+}
+
+#[derive(Serialize)]
+struct Measurement {
+    instruction: &'static str,
+    compute_units: u64,
+    threshold: u64,
+}
+
+fn baselines_path() -> PathBuf {
+    std::env::var(BASELINES_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("compute_units.json"))
+}
+
+fn report_dir() -> PathBuf {
+    std::env::var(REPORT_DIR_ENV)
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("../../target/compute-units"))
+}
+
+fn load_baselines() -> Baselines {
+    let path = baselines_path();
+    let json = fs::read_to_string(&path).unwrap_or_else(|err| panic!("Missing baselines {}: {err}", path.display()));
+
+    serde_json::from_str(&json).unwrap_or_else(|err| panic!("Invalid baselines {}: {err}", path.display()))
+}
+
+// Keeps the stated headroom, only the given measurements are replaced
+fn record_baselines(baselines: &mut Baselines, measured: &[(&'static str, u64)]) {
+    baselines.compute_units.extend(measured.iter().map(|&(instruction, compute_units)| (instruction.to_string(), compute_units)));
+
+    let path = baselines_path();
+    fs::write(&path, serde_json::to_string_pretty(&baselines).unwrap() + "\n").expect("Failed to write baselines");
+    println!("Compute unit baselines recorded to {}", path.display());
+}
+
+#[allow(clippy::result_large_err)]
+fn compute_units(instruction: &str, result: TransactionResult) -> u64 {
+    result.unwrap_or_else(|failed| panic!("{instruction} failed: {:?}", failed.err)).compute_units_consumed
+}
+
+// Each variant on a legacy SPL Token pair, no protocol fee
+fn run_benchmarks() -> Vec<(&'static str, u64)> {
+    let mut env = EscrowTestEnv::new().with_mints(6);
+    let mut measured = Vec::new();
+
+    let result = env.make(1, 10_000, 5_000, 0);
+    measured.push(("make", compute_units("make", result)));
+
+    // The first take creates both the taker's mint A and the maker's mint B accounts
+    let taker = env.new_taker();
+    let result = env.take(&taker);
+    measured.push(("take_init_atas", compute_units("take_init_atas", result)));
+
+    env.make(2, 10_000, 5_000, 0).unwrap();
+    let taker = env.new_taker();
+    CreateAssociatedTokenAccount::new(&mut env.program, &taker, &env.mint_a).owner(&taker.pubkey()).send().unwrap();
+    let result = env.take(&taker);
+    measured.push(("take", compute_units("take", result)));
+
+    env.make(3, 10_000, 5_000, 0).unwrap();
+    let result = env.take_partial(&taker, 2_500);
+    measured.push(("take_partial", compute_units("take_partial", result)));
+
+    env.make(4, 10_000, 5_000, 0).unwrap();
+    let result = env.refund();
+    measured.push(("refund", compute_units("refund", result)));
+
+    measured
+}
+
+fn write_report(measurements: &[Measurement]) -> PathBuf {
+    let dir = report_dir();
+    fs::create_dir_all(&dir).expect("Failed to create report directory");
+
+    let mut markdown = String::from("| Instruction | Compute units | Threshold | Headroom |\n|---|---:|---:|---:|\n");
+
+    for measurement in measurements {
+        let headroom = measurement.threshold as i64 - measurement.compute_units as i64;
+        markdown += &format!("| `{}` | {} | {} | {headroom} |\n", measurement.instruction, measurement.compute_units, measurement.threshold);
+    }
+
+    fs::write(dir.join("report.md"), markdown).expect("Failed to write markdown report");
+    fs::write(dir.join("report.json"), serde_json::to_string_pretty(measurements).unwrap() + "\n").expect("Failed to write JSON report");
+
+    dir
+}
+
+#[test]
+fn test_compute_units() {
+    let mut baselines = load_baselines();
+    let measured = run_benchmarks();
+
+    if std::env::var(RECORD_ENV).is_ok_and(|record| record == "1") {
+        baselines.compute_units.clear();
+        return record_baselines(&mut baselines, &measured);
+    }
+
+    let missing: Vec<(&'static str, u64)> = measured
+        .iter()
+        .filter(|(instruction, _)| !baselines.compute_units.contains_key(*instruction))
+        .copied()
+        .collect();
+
+    if !missing.is_empty() {
+        record_baselines(&mut baselines, &missing);
+    }
+
+    let measurements: Vec<Measurement> = measured
+        .into_iter()
+        .map(|(instruction, compute_units)| Measurement { instruction, compute_units, threshold: baselines.threshold(instruction) })
+        .collect();
+
+    let dir = write_report(&measurements);
+    println!("Compute unit report written to {}", dir.display());
+
+    let regressions: Vec<String> = measurements
+        .iter()
+        .filter(|measurement| measurement.compute_units > measurement.threshold)
+        .map(|measurement| format!("{}: {} CU, threshold {}", measurement.instruction, measurement.compute_units, measurement.threshold))
+        .collect();
+
+    assert!(regressions.is_empty(), "Compute units over threshold:\n{}", regressions.join("\n"));
+}
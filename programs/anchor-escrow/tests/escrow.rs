@@ -0,0 +1,3224 @@
+use {
+    anchor_lang::{
+        prelude::msg,
+        solana_program::program_pack::Pack,
+        AccountDeserialize,
+        InstructionData,
+        ToAccountMetas
+    }, anchor_spl::{
+        associated_token::{
+            self,
+            spl_associated_token_account
+        },
+        token::spl_token::{self, instruction::AuthorityType}
+    },
+    litesvm::{types::TransactionResult, LiteSVM},
+    litesvm_token::{
+        spl_token::ID as TOKEN_PROGRAM_ID,
+        CreateAssociatedTokenAccount,
+        CreateMint, MintTo, SetAuthority
+    },
+    solana_instruction::{AccountMeta, Instruction},
+    solana_keypair::Keypair,
+    solana_message::Message,
+    solana_native_token::LAMPORTS_PER_SOL,
+    solana_pubkey::Pubkey,
+    solana_sdk_ids::system_program::ID as SYSTEM_PROGRAM_ID,
+    solana_signer::Signer,
+    solana_transaction::Transaction,
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine},
+    spl_token_2022::{
+        extension::ExtensionType,
+        ID as TOKEN_2022_PROGRAM_ID
+    },
+    std::path::PathBuf
+};
+
+use anchor_escrow::events::{BasketTaken, BidAccepted, BidCancelled, BidPlaced, EscrowMade, EscrowRefunded, EscrowTaken, EscrowUpdated};
+use anchor_escrow::DepositChange;
+use anchor_escrow::state::{BasketLeg, DutchAuction, LockKind, MintStatus, NftTerms};
+use anchor_escrow_harness::{
+    config_address, deploy, fixtures::load_all_fixtures, get_token_amount, initialize_config_ix, mint_entry_address, setup, EscrowTestEnv, PROGRAM_ID,
+};
+
+fn warp_to_timestamp(program: &mut LiteSVM, unix_timestamp: i64) {
+    // Move the clock sysvar's wall-clock time without touching the slot
+    let mut clock = program.get_sysvar::<anchor_lang::solana_program::clock::Clock>();
+    clock.unix_timestamp = unix_timestamp;
+    program.set_sysvar(&clock);
+}
+
+fn create_token_2022_mint_with_extensions(
+    program: &mut LiteSVM,
+    payer: &Keypair,
+    authority: &Pubkey,
+    decimals: u8,
+    extensions: &[ExtensionType],
+    extension_ixs: impl FnOnce(&Pubkey) -> Vec<Instruction>,
+) -> Pubkey {
+    // litesvm-token only builds legacy SPL Token instructions, so Token-2022 mints are created by hand
+    let mint = Keypair::new();
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(extensions).unwrap();
+
+    let mut ixs = vec![solana_system_interface::instruction::create_account(
+        &payer.pubkey(),
+        &mint.pubkey(),
+        program.minimum_balance_for_rent_exemption(space),
+        space as u64,
+        &TOKEN_2022_PROGRAM_ID,
+    )];
+    // Mint extensions must be initialized before the mint itself
+    ixs.extend(extension_ixs(&mint.pubkey()));
+    ixs.push(spl_token_2022::instruction::initialize_mint2(
+        &TOKEN_2022_PROGRAM_ID, &mint.pubkey(), authority, None, decimals,
+    ).unwrap());
+
+    program.send_transaction(Transaction::new(&[payer, &mint], Message::new(&ixs, Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    mint.pubkey()
+}
+
+fn create_token_2022_mint(program: &mut LiteSVM, payer: &Keypair, authority: &Pubkey, decimals: u8) -> Pubkey {
+    create_token_2022_mint_with_extensions(program, payer, authority, decimals, &[], |_| vec![])
+}
+
+fn create_transfer_fee_mint(program: &mut LiteSVM, payer: &Keypair, authority: &Pubkey, decimals: u8, fee_basis_points: u16) -> Pubkey {
+    create_token_2022_mint_with_extensions(program, payer, authority, decimals, &[ExtensionType::TransferFeeConfig], |mint| vec![
+        spl_token_2022::extension::transfer_fee::instruction::initialize_transfer_fee_config(
+            &TOKEN_2022_PROGRAM_ID, mint, Some(authority), Some(authority), fee_basis_points, u64::MAX,
+        ).unwrap(),
+    ])
+}
+
+fn create_transfer_hook_mint(program: &mut LiteSVM, payer: &Keypair, authority: &Pubkey, decimals: u8) -> Pubkey {
+    create_token_2022_mint_with_extensions(program, payer, authority, decimals, &[ExtensionType::TransferHook], |mint| vec![
+        spl_token_2022::extension::transfer_hook::instruction::initialize(
+            &TOKEN_2022_PROGRAM_ID, mint, Some(*authority), Some(transfer_hook::ID),
+        ).unwrap(),
+    ])
+}
+
+fn load_transfer_hook(program: &mut LiteSVM) {
+    // Test-only program, kept out of `anchor build` and `anchor deploy`. Build it with
+    // `cargo build-sbf --manifest-path tests/programs/transfer-hook/Cargo.toml --sbf-out-dir target/deploy`
+    let so_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../target/deploy/transfer_hook.so");
+
+    let program_data = std::fs::read(so_path).expect("Failed to read transfer hook SO file");
+
+    program.add_program(transfer_hook::ID, &program_data);
+}
+
+fn mint_to_token_2022(program: &mut LiteSVM, authority: &Keypair, mint: &Pubkey, account: &Pubkey, amount: u64) {
+    let mint_ix = spl_token_2022::instruction::mint_to(
+        &TOKEN_2022_PROGRAM_ID, mint, account, &authority.pubkey(), &[], amount,
+    ).unwrap();
+
+    program.send_transaction(Transaction::new(&[authority], Message::new(&[mint_ix], Some(&authority.pubkey())), program.latest_blockhash())).unwrap();
+}
+
+fn find_event<E: anchor_lang::Event>(logs: &[String]) -> Option<E> {
+    // emit! writes each event as base64 "Program data:" log line, prefixed with its discriminator
+    logs.iter()
+        .filter_map(|log| log.strip_prefix("Program data: "))
+        .filter_map(|data| BASE64.decode(data).ok())
+        .find(|data| data.starts_with(E::DISCRIMINATOR))
+        .map(|data| E::try_from_slice(&data[E::DISCRIMINATOR.len()..]).unwrap())
+}
+
+fn set_mint_status(program: &mut LiteSVM, admin: &Keypair, mint: Pubkey, status: MintStatus) {
+    let set_mint_status_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::SetMintStatus {
+            admin: admin.pubkey(), config: config_address(), mint_entry: mint_entry_address(&mint), system_program: SYSTEM_PROGRAM_ID,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::SetMintStatus { mint, status }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[admin], Message::new(&[set_mint_status_ix], Some(&admin.pubkey())), program.latest_blockhash())).unwrap();
+}
+
+fn set_mint_policy(program: &mut LiteSVM, admin: &Keypair, allowlist_only: bool, reject_risky_mints: bool) {
+    let set_mint_policy_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::UpdateConfig { admin: admin.pubkey(), config: config_address() }.to_account_metas(None),
+        data: anchor_escrow::instruction::SetMintPolicy { allowlist_only, reject_risky_mints }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[admin], Message::new(&[set_mint_policy_ix], Some(&admin.pubkey())), program.latest_blockhash())).unwrap();
+}
+
+#[allow(clippy::result_large_err)]
+fn send_make(program: &mut LiteSVM, payer: &Keypair, mint_a: Pubkey, mint_b: Pubkey, args: anchor_escrow::instruction::Make) -> TransactionResult {
+    // Make from the payer's legacy SPL Token ATA, for tests that only care about the instruction arguments
+    let maker = payer.pubkey();
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &args.seed.to_le_bytes()], &PROGRAM_ID).0;
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker, mint_a, mint_b,
+            maker_ata_a: Some(associated_token::get_associated_token_address(&maker, &mint_a)),
+            escrow,
+            vault: associated_token::get_associated_token_address(&escrow, &mint_a),
+            associated_token_program: spl_associated_token_account::ID,
+            token_program_a: TOKEN_PROGRAM_ID, token_program_b: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: args.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[payer], Message::new(&[make_ix], Some(&maker)), program.latest_blockhash()))
+}
+
+fn create_nft(program: &mut LiteSVM, payer: &Keypair, owner: &Pubkey) -> (Pubkey, Pubkey) {
+    // A legacy SPL Token NFT: one unit of a 0-decimal mint whose authority is then revoked
+    let mint = CreateMint::new(program, payer).decimals(0).authority(&payer.pubkey()).send().unwrap();
+    let ata = CreateAssociatedTokenAccount::new(program, payer, &mint).owner(owner).send().unwrap();
+    MintTo::new(program, payer, &mint, &ata, 1).send().unwrap();
+    SetAuthority::new(program, payer, &mint, AuthorityType::MintTokens).send().unwrap();
+
+    (mint, ata)
+}
+
+fn create_collection(program: &mut LiteSVM, payer: &Keypair) -> Pubkey {
+    // Token-2022 collection: the group data lives on the mint itself, pointed to by its group pointer
+    let mint = Keypair::new();
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[ExtensionType::GroupPointer]).unwrap();
+    // initialize_group reallocs the mint, so fund it for the group data up front
+    let funded_space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[ExtensionType::GroupPointer, ExtensionType::TokenGroup]).unwrap();
+
+    let ixs = [
+        solana_system_interface::instruction::create_account(
+            &payer.pubkey(), &mint.pubkey(), program.minimum_balance_for_rent_exemption(funded_space), space as u64, &TOKEN_2022_PROGRAM_ID,
+        ),
+        spl_token_2022::extension::group_pointer::instruction::initialize(
+            &TOKEN_2022_PROGRAM_ID, &mint.pubkey(), Some(payer.pubkey()), Some(mint.pubkey()),
+        ).unwrap(),
+        spl_token_2022::instruction::initialize_mint2(&TOKEN_2022_PROGRAM_ID, &mint.pubkey(), &payer.pubkey(), None, 0).unwrap(),
+        spl_token_group_interface::instruction::initialize_group(
+            &TOKEN_2022_PROGRAM_ID, &mint.pubkey(), &mint.pubkey(), &payer.pubkey(), Some(payer.pubkey()), 100,
+        ),
+    ];
+
+    program.send_transaction(Transaction::new(&[payer, &mint], Message::new(&ixs, Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    mint.pubkey()
+}
+
+fn create_collection_member(program: &mut LiteSVM, payer: &Keypair, collection: &Pubkey, owner: &Pubkey) -> (Pubkey, Pubkey) {
+    // Token-2022 NFT carrying its own membership data, signed off by the collection's update authority
+    let mint = Keypair::new();
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[ExtensionType::GroupMemberPointer]).unwrap();
+    let funded_space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[ExtensionType::GroupMemberPointer, ExtensionType::TokenGroupMember]).unwrap();
+    let ata = associated_token::get_associated_token_address_with_program_id(owner, &mint.pubkey(), &TOKEN_2022_PROGRAM_ID);
+
+    let ixs = [
+        solana_system_interface::instruction::create_account(
+            &payer.pubkey(), &mint.pubkey(), program.minimum_balance_for_rent_exemption(funded_space), space as u64, &TOKEN_2022_PROGRAM_ID,
+        ),
+        spl_token_2022::extension::group_member_pointer::instruction::initialize(
+            &TOKEN_2022_PROGRAM_ID, &mint.pubkey(), Some(payer.pubkey()), Some(mint.pubkey()),
+        ).unwrap(),
+        spl_token_2022::instruction::initialize_mint2(&TOKEN_2022_PROGRAM_ID, &mint.pubkey(), &payer.pubkey(), None, 0).unwrap(),
+        spl_token_group_interface::instruction::initialize_member(
+            &TOKEN_2022_PROGRAM_ID, &mint.pubkey(), &mint.pubkey(), &payer.pubkey(), collection, &payer.pubkey(),
+        ),
+        spl_associated_token_account::instruction::create_associated_token_account(
+            &payer.pubkey(), owner, &mint.pubkey(), &TOKEN_2022_PROGRAM_ID,
+        ),
+        spl_token_2022::instruction::mint_to(&TOKEN_2022_PROGRAM_ID, &mint.pubkey(), &ata, &payer.pubkey(), &[], 1).unwrap(),
+        spl_token_2022::instruction::set_authority(
+            &TOKEN_2022_PROGRAM_ID, &mint.pubkey(), None, spl_token_2022::instruction::AuthorityType::MintTokens, &payer.pubkey(), &[],
+        ).unwrap(),
+    ];
+
+    program.send_transaction(Transaction::new(&[payer, &mint], Message::new(&ixs, Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    (mint.pubkey(), ata)
+}
+
+#[test]
+fn test_make() {
+
+    // Setup the test environment by initializing LiteSVM and creating a payer keypair
+    let (mut program, payer) = setup();
+
+    // Get the maker's public key from the payer keypair
+    let maker = payer.pubkey();
+    
+    // Create two mints (Mint A and Mint B) with 6 decimal places and the maker as the authority
+    let mint_a = CreateMint::new(&mut program, &payer)
+        .decimals(6)
+        .authority(&maker)
+        .send()
+        .unwrap();
+    msg!("Mint A: {}\n", mint_a);
+
+    let mint_b = CreateMint::new(&mut program, &payer)
+        .decimals(6)
+        .authority(&maker)
+        .send()
+        .unwrap();
+    msg!("Mint B: {}\n", mint_b);
+
+    // Create the maker's associated token account for Mint A
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a)
+        .owner(&maker).send().unwrap();
+    msg!("Maker ATA A: {}\n", maker_ata_a);
+
+    // Derive the PDA for the escrow account using the maker's public key and a seed value
+    let escrow = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &123u64.to_le_bytes()],
+        &PROGRAM_ID
+    ).0;
+    msg!("Escrow PDA: {}\n", escrow);
+
+    // Derive the PDA for the vault associated token account using the escrow PDA and Mint A
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+    msg!("Vault PDA: {}\n", vault);
+
+    // Define program IDs for associated token program, token program, and system program
+    let asspciated_token_program = spl_associated_token_account::ID;
+    let token_program = TOKEN_PROGRAM_ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    // Mint 1,000 tokens (with 6 decimal places) of Mint A to the maker's associated token account
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000)
+        .send()
+        .unwrap();
+
+    // Create the "Make" instruction to deposit tokens into the escrow
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a: Some(maker_ata_a),
+            escrow,
+            vault,
+            associated_token_program: asspciated_token_program,
+            token_program_a: token_program,
+            token_program_b: token_program,
+            system_program,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Make {deposit: 10, seed: 123u64, receive: 10, lock_kind: LockKind::Slot, lock_period: 1, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None }.data(),
+    };
+
+    // Create and send the transaction containing the "Make" instruction
+    let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+    let recent_blockhash = program.latest_blockhash();
+
+    let transaction = Transaction::new(&[&payer], message, recent_blockhash);
+
+    // Send the transaction and capture the result
+    let tx = program.send_transaction(transaction).unwrap();
+
+    // Log transaction details
+    msg!("\n\nMake transaction sucessfull");
+    msg!("CUs Consumed: {}", tx.compute_units_consumed);
+    msg!("Tx Signature: {}", tx.signature);
+
+    // Verify the vault account and escrow account data after the "Make" instruction
+    let vault_account = program.get_account(&vault).unwrap();
+    let vault_data = spl_token::state::Account::unpack(&vault_account.data).unwrap();
+    assert_eq!(vault_data.amount, 10);
+    assert_eq!(vault_data.owner, escrow);
+    assert_eq!(vault_data.mint, mint_a);
+
+    let escrow_account = program.get_account(&escrow).unwrap();
+    let escrow_data = anchor_escrow::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+    assert_eq!(escrow_data.seed, 123u64);
+    assert_eq!(escrow_data.maker, maker);
+    assert_eq!(escrow_data.mint_a, mint_a);
+    assert_eq!(escrow_data.mint_b, mint_b);
+    assert_eq!(escrow_data.deposit, 10);
+    assert_eq!(escrow_data.receive, 10);
+
+    let event = find_event::<EscrowMade>(&tx.logs).expect("EscrowMade event should be emitted");
+    assert_eq!(event.seed, 123u64);
+    assert_eq!(event.maker, maker);
+    assert_eq!(event.mint_a, mint_a);
+    assert_eq!(event.mint_b, mint_b);
+    assert_eq!(event.deposit, 10);
+    assert_eq!(event.receive, 10);
+    assert_eq!(event.slot, program.get_sysvar::<anchor_lang::prelude::Clock>().slot);
+}
+
+#[test]
+fn test_take() {
+    // Setup the test environment
+    let (mut program, payer) = setup();
+
+    // Create maker and taker keypairs
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+
+    // Airdrop SOL to taker
+    program
+        .airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL)
+        .expect("Failed to airdrop SOL to taker");
+
+    // Create two mints (Mint A and Mint B)
+    let mint_a = CreateMint::new(&mut program, &payer)
+        .decimals(6)
+        .authority(&maker)
+        .send()
+        .unwrap();
+    msg!("Mint A: {}\n", mint_a);
+
+    let mint_b = CreateMint::new(&mut program, &payer)
+        .decimals(6)
+        .authority(&maker)
+        .send()
+        .unwrap();
+    msg!("Mint B: {}\n", mint_b);
+
+    // Create maker's ATA for Mint A
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a)
+        .owner(&maker)
+        .send()
+        .unwrap();
+    msg!("Maker ATA A: {}\n", maker_ata_a);
+
+    // Create taker's ATA for Mint B
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b)
+        .owner(&taker.pubkey())
+        .send()
+        .unwrap();
+    msg!("Taker ATA B: {}\n", taker_ata_b);
+
+    // Mint tokens to maker and taker
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000)
+        .send()
+        .unwrap();
+
+    MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000)
+        .send()
+        .unwrap();
+
+    // Derive escrow and vault PDAs
+    let escrow = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &123u64.to_le_bytes()],
+        &PROGRAM_ID
+    ).0;
+    msg!("Escrow PDA: {}\n", escrow);
+
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+    msg!("Vault PDA: {}\n", vault);
+
+    let associated_token_program = spl_associated_token_account::ID;
+    let token_program = TOKEN_PROGRAM_ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    // Execute make instruction (maker deposits 10 tokens of Mint A, wants 20 tokens of Mint B)
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a: Some(maker_ata_a),
+            escrow,
+            vault,
+            associated_token_program,
+            token_program_a: token_program,
+            token_program_b: token_program,
+            system_program,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Make { deposit: 10, seed: 123u64, receive: 20, lock_kind: LockKind::Slot, lock_period: 1, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None }.data(),
+    };
+
+    let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+    let recent_blockhash = program.latest_blockhash();
+    let transaction = Transaction::new(&[&payer], message, recent_blockhash);
+    program.send_transaction(transaction).unwrap();
+
+    msg!("Make transaction successful");
+
+    // Warp forward by 2 slots to pass the lock period
+    let current_slot = program.get_sysvar::<anchor_lang::solana_program::clock::Clock>().slot;
+    program.warp_to_slot(current_slot + 2);
+
+    // Derive taker's ATA for Mint A and maker's ATA for Mint B
+    let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+
+    // Execute take instruction (taker sends 20 tokens of Mint B, receives 10 tokens of Mint A)
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(),
+            maker,
+            mint_a,
+            mint_b,
+            taker_ata_a,
+            taker_ata_b: Some(taker_ata_b),
+            maker_ata_b: Some(maker_ata_b),
+            escrow,
+            vault,
+            associated_token_program,
+            token_program_a: token_program,
+            token_program_b: token_program,
+            system_program,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    let message = Message::new(&[take_ix], Some(&taker.pubkey()));
+    let recent_blockhash = program.latest_blockhash();
+    let transaction = Transaction::new(&[&taker], message, recent_blockhash);
+    let tx = program.send_transaction(transaction).unwrap();
+
+    msg!("\n\nTake transaction successful");
+    msg!("CUs Consumed: {}", tx.compute_units_consumed);
+    msg!("Tx Signature: {}", tx.signature);
+
+    // Verify taker received tokens from vault
+    let taker_ata_a_account = program.get_account(&taker_ata_a).unwrap();
+    let taker_ata_a_data = spl_token::state::Account::unpack(&taker_ata_a_account.data).unwrap();
+    assert_eq!(taker_ata_a_data.amount, 10, "Taker should have received 10 tokens of Mint A");
+
+    // Verify maker received tokens from taker
+    let maker_ata_b_account = program.get_account(&maker_ata_b).unwrap();
+    let maker_ata_b_data = spl_token::state::Account::unpack(&maker_ata_b_account.data).unwrap();
+    assert_eq!(maker_ata_b_data.amount, 20, "Maker should have received 20 tokens of Mint B");
+
+    // Verify the take was announced to indexers
+    let event = find_event::<EscrowTaken>(&tx.logs).expect("EscrowTaken event should be emitted");
+    assert_eq!(event.maker, maker);
+    assert_eq!(event.taker, taker.pubkey());
+    assert_eq!(event.mint_a, mint_a);
+    assert_eq!(event.mint_b, mint_b);
+    assert_eq!(event.amount_a, 10);
+    assert_eq!(event.amount_b, 20);
+    assert_eq!(event.remaining_receive, 0);
+
+    // Verify vault is closed (check if account exists and has 0 lamports)
+    match program.get_account(&vault) {
+        None => msg!("Vault account is None (properly closed)"),
+        Some(acc) => {
+            msg!("Vault account exists with {} lamports", acc.lamports);
+            msg!("Vault owner: {}", acc.owner);
+            // In LiteSVM, closed accounts might still exist with 0 lamports
+            assert_eq!(acc.lamports, 0, "Vault should have 0 lamports (closed)");
+        }
+    }
+
+    // Verify escrow is closed (check if account exists and has 0 lamports)
+    match program.get_account(&escrow) {
+        None => msg!("Escrow account is None (properly closed)"),
+        Some(acc) => {
+            msg!("Escrow account exists with {} lamports", acc.lamports);
+            msg!("Escrow owner: {}", acc.owner);
+            // In LiteSVM, closed accounts might still exist with 0 lamports
+            assert_eq!(acc.lamports, 0, "Escrow should have 0 lamports (closed)");
+        }
+    }
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_refund() {
+    // Setup the test environment
+    let (mut program, payer) = setup();
+
+    // Get the maker's public key from the payer keypair
+    let maker = payer.pubkey();
+
+    // Create two mints (Mint A and Mint B)
+    let mint_a = CreateMint::new(&mut program, &payer)
+        .decimals(6)
+        .authority(&maker)
+        .send()
+        .unwrap();
+    msg!("Mint A: {}\n", mint_a);
+
+    let mint_b = CreateMint::new(&mut program, &payer)
+        .decimals(6)
+        .authority(&maker)
+        .send()
+        .unwrap();
+    msg!("Mint B: {}\n", mint_b);
+
+    // Create the maker's associated token account for Mint A
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a)
+        .owner(&maker)
+        .send()
+        .unwrap();
+    msg!("Maker ATA A: {}\n", maker_ata_a);
+
+    // Mint 1,000 tokens to maker
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000)
+        .send()
+        .unwrap();
+
+    // Store maker's initial balance
+    let initial_balance = {
+        let maker_ata_a_account = program.get_account(&maker_ata_a).unwrap();
+        let maker_ata_a_data = spl_token::state::Account::unpack(&maker_ata_a_account.data).unwrap();
+        maker_ata_a_data.amount
+    };
+    msg!("Maker initial balance: {}", initial_balance);
+
+    // Derive escrow and vault PDAs
+    let escrow = Pubkey::find_program_address(
+        &[b"escrow", maker.as_ref(), &123u64.to_le_bytes()],
+        &PROGRAM_ID
+    ).0;
+    msg!("Escrow PDA: {}\n", escrow);
+
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+    msg!("Vault PDA: {}\n", vault);
+
+    let associated_token_program = spl_associated_token_account::ID;
+    let token_program = TOKEN_PROGRAM_ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    // Execute make instruction (maker deposits 100 tokens)
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker,
+            mint_a,
+            mint_b,
+            maker_ata_a: Some(maker_ata_a),
+            escrow,
+            vault,
+            associated_token_program,
+            token_program_a: token_program,
+            token_program_b: token_program,
+            system_program,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Make { deposit: 100, seed: 123u64, receive: 50, lock_kind: LockKind::Slot, lock_period: 1, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None }.data(),
+    };
+
+    let message = Message::new(&[make_ix], Some(&payer.pubkey()));
+    let recent_blockhash = program.latest_blockhash();
+    let transaction = Transaction::new(&[&payer], message, recent_blockhash);
+    program.send_transaction(transaction).unwrap();
+
+    msg!("Make transaction successful");
+
+    // Verify tokens were deposited to vault
+    let vault_account = program.get_account(&vault).unwrap();
+    let vault_data = spl_token::state::Account::unpack(&vault_account.data).unwrap();
+    assert_eq!(vault_data.amount, 100, "Vault should have 100 tokens");
+
+    // Execute refund instruction
+    let refund_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Refund {
+            maker,
+            mint_a,
+            maker_ata_a: Some(maker_ata_a),
+            escrow,
+            vault,
+            token_program,
+            system_program,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Refund {}.data(),
+    };
+
+    let message = Message::new(&[refund_ix], Some(&payer.pubkey()));
+    let recent_blockhash = program.latest_blockhash();
+    let transaction = Transaction::new(&[&payer], message, recent_blockhash);
+    let tx = program.send_transaction(transaction).unwrap();
+
+    msg!("\n\nRefund transaction successful");
+    msg!("CUs Consumed: {}", tx.compute_units_consumed);
+    msg!("Tx Signature: {}", tx.signature);
+
+    // Verify maker got all tokens back
+    let maker_ata_a_account = program.get_account(&maker_ata_a).unwrap();
+    let maker_ata_a_data = spl_token::state::Account::unpack(&maker_ata_a_account.data).unwrap();
+    assert_eq!(maker_ata_a_data.amount, initial_balance, "Maker should have all tokens back");
+
+    let event = find_event::<EscrowRefunded>(&tx.logs).expect("EscrowRefunded event should be emitted");
+    assert_eq!(event.maker, maker);
+    assert_eq!(event.mint_a, mint_a);
+    assert_eq!(event.amount, 100);
+
+    // Verify vault is closed (check if account exists and has 0 lamports)
+    match program.get_account(&vault) {
+        None => msg!("Vault account is None (properly closed)"),
+        Some(acc) => {
+            msg!("Vault account exists with {} lamports", acc.lamports);
+            msg!("Vault owner: {}", acc.owner);
+            // In LiteSVM, closed accounts might still exist with 0 lamports
+            assert_eq!(acc.lamports, 0, "Vault should have 0 lamports (closed)");
+        }
+    }
+
+    // Verify escrow is closed (check if account exists and has 0 lamports)
+    match program.get_account(&escrow) {
+        None => msg!("Escrow account is None (properly closed)"),
+        Some(acc) => {
+            msg!("Escrow account exists with {} lamports", acc.lamports);
+            msg!("Escrow owner: {}", acc.owner);
+            // In LiteSVM, closed accounts might still exist with 0 lamports
+            assert_eq!(acc.lamports, 0, "Escrow should have 0 lamports (closed)");
+        }
+    }
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_before_lock_expires() {
+    // Setup the test environment
+    let mut env = EscrowTestEnv::new().with_mints(6);
+    let taker = env.new_taker();
+
+    // Make with lock_period of 5 slots
+    env.make(456, 10, 20, 5).unwrap();
+
+    msg!("Make transaction successful with lock_period = 5");
+
+    // DO NOT warp time - try to take immediately (should fail with EscrowLocked error)
+    let result = env.take(&taker);
+
+    // Assert that the transaction failed with EscrowLocked error (code 6000)
+    assert!(result.is_err(), "Take should fail before lock period expires");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1770") || error_msg.contains("6000"),
+        "Error should be EscrowLocked (6000/0x1770), got: {}", error_msg);
+
+    msg!("Take correctly failed with EscrowLocked error");
+
+    // Verify escrow and vault still exist
+    assert!(env.exists(&env.escrow()), "Escrow should still exist");
+    assert!(env.exists(&env.vault()), "Vault should still exist");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_refund_before_commit_expires() {
+    // Setup the test environment
+    let mut env = EscrowTestEnv::new().with_mints(6);
+    let maker = env.maker.pubkey();
+
+    // Make with commit_period of 5 slots
+    env.make_with(anchor_escrow::instruction::Make {
+        deposit: 10, seed: 457u64, receive: 20, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 5, expires_at: None, taker: None, nft: None, auction: None,
+    }).unwrap();
+
+    msg!("Make transaction successful with commit_period = 5");
+
+    let start_time = env.escrow_state().start_time;
+
+    // DO NOT warp time - try to refund immediately
+    let result = env.refund();
+
+    // Assert that the transaction failed with RefundLocked error (code 6006)
+    assert!(result.is_err(), "Refund should fail before commit period expires");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1776") || error_msg.contains("6006"),
+        "Error should be RefundLocked (6006/0x1776), got: {}", error_msg);
+
+    msg!("Refund correctly failed with RefundLocked error");
+
+    // Warp to EXACTLY start_time + 5 and retry
+    env.warp((start_time + 5) as u64 - env.slot());
+
+    env.refund().unwrap();
+
+    assert_eq!(env.balance_a(&maker), 1000000000, "Maker should have all tokens back");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_exactly_at_lock_expiry() {
+    // Setup the test environment
+    let mut env = EscrowTestEnv::new().with_mints(6);
+    let taker = env.new_taker();
+
+    // Make with lock_period = 1
+    env.make(789, 10, 20, 1).unwrap();
+
+    msg!("Make transaction successful with lock_period = 1");
+
+    // Read start_time from escrow
+    let start_time = env.escrow_state().start_time;
+
+    msg!("Escrow start_time: {}", start_time);
+
+    // Warp to EXACTLY start_time + 1 (minimum to pass)
+    env.warp((start_time + 1) as u64 - env.slot());
+    msg!("Warped to slot: {}", start_time + 1);
+
+    // Execute take - should succeed
+    let tx = env.take(&taker).unwrap();
+
+    msg!("Take transaction successful at exact lock expiry!");
+    msg!("CUs Consumed: {}", tx.compute_units_consumed);
+
+    // Verify token transfers
+    assert_eq!(env.balance_a(&taker.pubkey()), 10, "Taker should have 10 tokens");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_with_unix_timestamp_lock() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b).owner(&taker.pubkey()).send().unwrap();
+
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000).send().unwrap();
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &333u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+
+    let associated_token_program = spl_associated_token_account::ID;
+    let token_program = TOKEN_PROGRAM_ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    // Make with a one hour wall-clock lock
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker, mint_a, mint_b, maker_ata_a: Some(maker_ata_a), escrow, vault,
+            associated_token_program, token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Make { deposit: 10, seed: 333u64, receive: 20, lock_kind: LockKind::UnixTimestamp, lock_period: 3600, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    let escrow_account = program.get_account(&escrow).unwrap();
+    let escrow_data = anchor_escrow::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+    let start_time = escrow_data.start_time;
+    assert_eq!(escrow_data.lock_kind, LockKind::UnixTimestamp);
+    assert_eq!(start_time, program.get_sysvar::<anchor_lang::solana_program::clock::Clock>().unix_timestamp);
+
+    msg!("Escrow start_time (unix): {}", start_time);
+
+    let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b),
+            maker_ata_b: Some(maker_ata_b), escrow, vault, associated_token_program, token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    // Warp far ahead in slots only; a timestamp lock must ignore slots
+    let current_slot = program.get_sysvar::<anchor_lang::solana_program::clock::Clock>().slot;
+    program.warp_to_slot(current_slot + 100_000);
+    warp_to_timestamp(&mut program, start_time + 3599);
+
+    let result = program.send_transaction(Transaction::new(&[&taker], Message::new(std::slice::from_ref(&take_ix), Some(&taker.pubkey())), program.latest_blockhash()));
+    assert!(result.is_err(), "Take should fail one second before the lock expires");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1770") || error_msg.contains("6000"),
+        "Error should be EscrowLocked (6000/0x1770), got: {}", error_msg);
+
+    msg!("Take correctly failed with EscrowLocked error");
+
+    // Warp to EXACTLY start_time + 3600 seconds and retry with a fresh blockhash
+    warp_to_timestamp(&mut program, start_time + 3600);
+    program.expire_blockhash();
+
+    program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash())).unwrap();
+
+    let taker_ata_a_account = program.get_account(&taker_ata_a).unwrap();
+    let taker_ata_a_data = spl_token::state::Account::unpack(&taker_ata_a_account.data).unwrap();
+    assert_eq!(taker_ata_a_data.amount, 10, "Taker should have 10 tokens");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_far_future() {
+    // Setup
+    let mut env = EscrowTestEnv::new().with_mints(6);
+    let taker = env.new_taker();
+
+    // Make with lock_period = 10
+    env.make(999, 10, 20, 10).unwrap();
+
+    msg!("Make transaction successful with lock_period = 10");
+
+    // Warp 1000 slots into the future (way past lock period)
+    env.warp(1000);
+
+    msg!("Warped +1000 slots into the future");
+
+    // Take should succeed
+    let tx = env.take(&taker).unwrap();
+
+    msg!("Take successful far in the future!");
+    msg!("CUs Consumed: {}", tx.compute_units_consumed);
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_different_lock_periods() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+
+    // Airdrop SOL to taker
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b).owner(&taker.pubkey()).send().unwrap();
+
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 2000000000).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 2000000000).send().unwrap();
+
+    // Create escrow #1 with lock_period = 1
+    let escrow1 = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &100u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault1 = associated_token::get_associated_token_address(&escrow1, &mint_a);
+
+    // Create escrow #2 with lock_period = 100
+    let escrow2 = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &200u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault2 = associated_token::get_associated_token_address(&escrow2, &mint_a);
+
+    let associated_token_program = spl_associated_token_account::ID;
+    let token_program = TOKEN_PROGRAM_ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    // Make escrow #1 (lock_period = 1)
+    let make_ix1 = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker, mint_a, mint_b, maker_ata_a: Some(maker_ata_a), escrow: escrow1, vault: vault1,
+            associated_token_program, token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Make { deposit: 50, seed: 100u64, receive: 25, lock_kind: LockKind::Slot, lock_period: 1, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix1], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+    msg!("Escrow #1 created with lock_period = 1");
+
+    // Make escrow #2 (lock_period = 100)
+    let make_ix2 = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker, mint_a, mint_b, maker_ata_a: Some(maker_ata_a), escrow: escrow2, vault: vault2,
+            associated_token_program, token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Make { deposit: 50, seed: 200u64, receive: 25, lock_kind: LockKind::Slot, lock_period: 100, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix2], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+    msg!("Escrow #2 created with lock_period = 100");
+
+    // Warp forward +2 slots
+    let current_slot = program.get_sysvar::<anchor_lang::solana_program::clock::Clock>().slot;
+    program.warp_to_slot(current_slot + 2);
+
+    msg!("Warped +2 slots");
+
+    // Take escrow #1 - should succeed
+    let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+
+    let take_ix1 = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b),
+            maker_ata_b: Some(maker_ata_b), escrow: escrow1, vault: vault1,
+            associated_token_program, token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix1], Some(&taker.pubkey())), program.latest_blockhash())).unwrap();
+    msg!("Escrow #1 taken successfully!");
+
+    // Warp forward +99 more slots (total +101 from start)
+    // Escrow #2 has lock_period=100, so at slot 101 it should be unlocked
+    let current_slot = program.get_sysvar::<anchor_lang::solana_program::clock::Clock>().slot;
+    program.warp_to_slot(current_slot + 99);
+
+    msg!("Warped +99 more slots");
+
+    // Take escrow #2 - should now succeed (fetch new blockhash after time warp)
+    let take_ix2_retry = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b),
+            maker_ata_b: Some(maker_ata_b), escrow: escrow2, vault: vault2,
+            associated_token_program, token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    // Get new blockhash after time warp to avoid AlreadyProcessed error
+    let new_blockhash = program.latest_blockhash();
+    program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix2_retry], Some(&taker.pubkey())), new_blockhash)).unwrap();
+    msg!("Escrow #2 taken successfully after lock period!");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_partial() {
+    // Setup
+    let mut env = EscrowTestEnv::new().with_mints(6);
+    let maker = env.maker.pubkey();
+    let taker = env.new_taker();
+
+    // Make: deposit 100 of Mint A, wants 50 of Mint B
+    env.make(321, 100, 50, 0).unwrap();
+
+    // First fill: pay 20 of 50 Mint B, receive 40 of 100 Mint A
+    let tx = env.take_partial(&taker, 20).unwrap();
+
+    msg!("First partial fill successful");
+
+    let event = find_event::<EscrowTaken>(&tx.logs).expect("EscrowTaken event should be emitted");
+    assert_eq!(event.amount_a, 40);
+    assert_eq!(event.amount_b, 20);
+    assert_eq!(event.remaining_receive, 30);
+
+    assert_eq!(env.balance_a(&taker.pubkey()), 40, "Taker should have received 40 tokens of Mint A");
+    assert_eq!(env.vault_balance(), 60, "Vault should still hold 60 tokens of Mint A");
+
+    let escrow_data = env.escrow_state();
+    assert_eq!(escrow_data.deposit, 60);
+    assert_eq!(escrow_data.receive, 30);
+
+    // Second fill: pay the remaining 30 Mint B, which closes the escrow
+    env.take_partial(&taker, 30).unwrap();
+
+    msg!("Final partial fill successful");
+
+    assert_eq!(env.balance_a(&taker.pubkey()), 100, "Taker should have received all 100 tokens of Mint A");
+    assert_eq!(env.balance_b(&maker), 50, "Maker should have received 50 tokens of Mint B");
+
+    // In LiteSVM, closed accounts might still exist with 0 lamports
+    assert!(!env.exists(&env.vault()), "Vault should be closed");
+    assert!(!env.exists(&env.escrow()), "Escrow should be closed");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_partial_exceeds_remaining() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b).owner(&taker.pubkey()).send().unwrap();
+
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000).send().unwrap();
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &654u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+
+    let associated_token_program = spl_associated_token_account::ID;
+    let token_program = TOKEN_PROGRAM_ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker, mint_a, mint_b, maker_ata_a: Some(maker_ata_a), escrow, vault,
+            associated_token_program, token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Make { deposit: 100, seed: 654u64, receive: 50, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+
+    // Attempt to pay more than the escrow asks for (should fail with InvalidFillAmount)
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b),
+            maker_ata_b: Some(maker_ata_b), escrow, vault, associated_token_program, token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::TakePartial { amount_b: 51, min_amount_a: 0, referrer_fee_bps: 0 }.data(),
+    };
+
+    let result = program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash()));
+
+    assert!(result.is_err(), "Take should fail when overpaying the escrow");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1771") || error_msg.contains("6001"),
+        "Error should be InvalidFillAmount (6001/0x1771), got: {}", error_msg);
+
+    // Verify escrow and vault are untouched
+    let vault_account = program.get_account(&vault).unwrap();
+    let vault_data = spl_token::state::Account::unpack(&vault_account.data).unwrap();
+    assert_eq!(vault_data.amount, 100, "Vault should still hold all 100 tokens");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_after_expiry_and_refund_expired() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+    let cranker = Keypair::new();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+    program.airdrop(&cranker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b).owner(&taker.pubkey()).send().unwrap();
+
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000).send().unwrap();
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &111u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+
+    let associated_token_program = spl_associated_token_account::ID;
+    let token_program = TOKEN_PROGRAM_ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    // Make with an expiry 10 slots from now
+    let current_slot = program.get_sysvar::<anchor_lang::solana_program::clock::Clock>().slot;
+    let expires_at = current_slot as i64 + 10;
+
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker, mint_a, mint_b, maker_ata_a: Some(maker_ata_a), escrow, vault,
+            associated_token_program, token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Make { deposit: 10, seed: 111u64, receive: 20, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: Some(expires_at), taker: None, nft: None, auction: None }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Make transaction successful with expires_at = {}", expires_at);
+
+    let refund_expired_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::RefundExpired {
+            payer: cranker.pubkey(), maker, mint_a, maker_ata_a: Some(maker_ata_a), escrow, vault,
+            associated_token_program, token_program, system_program,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::RefundExpired {}.data(),
+    };
+
+    // Permissionless refund before expiry should fail with EscrowNotExpired
+    let result = program.send_transaction(Transaction::new(&[&cranker], Message::new(std::slice::from_ref(&refund_expired_ix), Some(&cranker.pubkey())), program.latest_blockhash()));
+    assert!(result.is_err(), "Refund should fail before the escrow expires");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1774") || error_msg.contains("6004"),
+        "Error should be EscrowNotExpired (6004/0x1774), got: {}", error_msg);
+
+    // Warp to exactly the expiry slot
+    program.warp_to_slot(expires_at as u64);
+
+    let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+
+    // Take should fail with EscrowExpired
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b),
+            maker_ata_b: Some(maker_ata_b), escrow, vault, associated_token_program, token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    let result = program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash()));
+    assert!(result.is_err(), "Take should fail once the escrow has expired");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1773") || error_msg.contains("6003"),
+        "Error should be EscrowExpired (6003/0x1773), got: {}", error_msg);
+
+    msg!("Take correctly failed with EscrowExpired error");
+
+    // A third party can now return the funds to the maker (fresh blockhash, the failed attempt was recorded)
+    program.expire_blockhash();
+    let tx = program.send_transaction(Transaction::new(&[&cranker], Message::new(&[refund_expired_ix], Some(&cranker.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("\n\nRefund expired transaction successful");
+    msg!("CUs Consumed: {}", tx.compute_units_consumed);
+
+    let maker_ata_a_account = program.get_account(&maker_ata_a).unwrap();
+    let maker_ata_a_data = spl_token::state::Account::unpack(&maker_ata_a_account.data).unwrap();
+    assert_eq!(maker_ata_a_data.amount, 1000000000, "Maker should have all tokens back");
+
+    // In LiteSVM, closed accounts might still exist with 0 lamports
+    assert_eq!(program.get_account(&vault).map_or(0, |acc| acc.lamports), 0, "Vault should be closed");
+    assert_eq!(program.get_account(&escrow).map_or(0, |acc| acc.lamports), 0, "Escrow should be closed");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_make_rejects_expiry_before_unlock() {
+    let mut env = EscrowTestEnv::new().with_mints(6);
+
+    env.warp(100);
+    let now = env.slot() as i64;
+
+    let make_args = |seed: u64, expires_at: i64| anchor_escrow::instruction::Make {
+        deposit: 10, seed, receive: 20, lock_kind: LockKind::Slot, lock_period: 10, commit_period: 0, expires_at: Some(expires_at), taker: None, nft: None, auction: None,
+    };
+
+    // Already expired, expiring as the lock ends, and expiring before it (should fail with InvalidExpiry)
+    for (seed, expires_at) in [(121u64, now - 1), (122, now + 10), (123, now + 5)] {
+        let result = env.make_with(make_args(seed, expires_at));
+
+        assert!(result.is_err(), "Make should reject an expiry at {expires_at} for a lock ending at {}", now + 10);
+        let error_msg = format!("{:?}", result.unwrap_err());
+        assert!(error_msg.contains("0x1790") || error_msg.contains("6032"),
+            "Error should be InvalidExpiry (6032/0x1790), got: {}", error_msg);
+        assert!(!env.exists(&env.escrow()), "No escrow should be created");
+    }
+
+    // Expiring one slot after the lock ends leaves a slot to take it
+    env.make_with(make_args(124, now + 11)).unwrap();
+    assert_eq!(env.escrow_state().expires_at, Some(now + 11));
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_by_designated_taker_only() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+    let intruder = Keypair::new();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+    program.airdrop(&intruder.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b).owner(&taker.pubkey()).send().unwrap();
+    let intruder_ata_b = CreateAssociatedTokenAccount::new(&mut program, &intruder, &mint_b).owner(&intruder.pubkey()).send().unwrap();
+
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_b, &intruder_ata_b, 1000000000).send().unwrap();
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &222u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+
+    let associated_token_program = spl_associated_token_account::ID;
+    let token_program = TOKEN_PROGRAM_ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    // Make a private escrow that only `taker` may fill
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker, mint_a, mint_b, maker_ata_a: Some(maker_ata_a), escrow, vault,
+            associated_token_program, token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Make { deposit: 10, seed: 222u64, receive: 20, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: Some(taker.pubkey()), nft: None, auction: None }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+
+    // Another signer tries to front-run the designated taker (should fail with UnauthorizedTaker)
+    let intruder_ata_a = associated_token::get_associated_token_address(&intruder.pubkey(), &mint_a);
+
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: intruder.pubkey(), maker, mint_a, mint_b, taker_ata_a: intruder_ata_a, taker_ata_b: Some(intruder_ata_b),
+            maker_ata_b: Some(maker_ata_b), escrow, vault, associated_token_program, token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    let result = program.send_transaction(Transaction::new(&[&intruder], Message::new(&[take_ix], Some(&intruder.pubkey())), program.latest_blockhash()));
+    assert!(result.is_err(), "Take should fail for a signer other than the designated taker");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1775") || error_msg.contains("6005"),
+        "Error should be UnauthorizedTaker (6005/0x1775), got: {}", error_msg);
+
+    msg!("Take correctly failed with UnauthorizedTaker error");
+
+    // The designated taker can fill it
+    let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b),
+            maker_ata_b: Some(maker_ata_b), escrow, vault, associated_token_program, token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash())).unwrap();
+
+    let taker_ata_a_account = program.get_account(&taker_ata_a).unwrap();
+    let taker_ata_a_data = spl_token::state::Account::unpack(&taker_ata_a_account.data).unwrap();
+    assert_eq!(taker_ata_a_data.amount, 10, "Designated taker should have received 10 tokens of Mint A");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_mixed_token_programs() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    // Mint A on legacy SPL Token, Mint B on Token-2022
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = create_token_2022_mint(&mut program, &payer, &maker, 9);
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b)
+        .owner(&taker.pubkey())
+        .token_program_id(&TOKEN_2022_PROGRAM_ID)
+        .send()
+        .unwrap();
+
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+    mint_to_token_2022(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000);
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &606u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address_with_program_id(&escrow, &mint_a, &TOKEN_PROGRAM_ID);
+
+    let associated_token_program = spl_associated_token_account::ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker, mint_a, mint_b, maker_ata_a: Some(maker_ata_a), escrow, vault, associated_token_program,
+            token_program_a: TOKEN_PROGRAM_ID, token_program_b: TOKEN_2022_PROGRAM_ID, system_program,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Make { deposit: 10, seed: 606u64, receive: 20, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Make transaction successful with mixed token programs");
+
+    let taker_ata_a = associated_token::get_associated_token_address_with_program_id(&taker.pubkey(), &mint_a, &TOKEN_PROGRAM_ID);
+    let maker_ata_b = associated_token::get_associated_token_address_with_program_id(&maker, &mint_b, &TOKEN_2022_PROGRAM_ID);
+
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b), maker_ata_b: Some(maker_ata_b), escrow, vault, associated_token_program,
+            token_program_a: TOKEN_PROGRAM_ID, token_program_b: TOKEN_2022_PROGRAM_ID, system_program,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    let tx = program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Take transaction successful with mixed token programs");
+    msg!("CUs Consumed: {}", tx.compute_units_consumed);
+
+    assert_eq!(get_token_amount(&program, &taker_ata_a), 10, "Taker should have received 10 tokens of Mint A");
+    assert_eq!(get_token_amount(&program, &maker_ata_b), 20, "Maker should have received 20 tokens of Mint B");
+    assert_eq!(program.get_account(&maker_ata_b).unwrap().owner, TOKEN_2022_PROGRAM_ID, "Maker ATA B should be a Token-2022 account");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_with_transfer_fee_mints() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    // Mint A charges 1% and Mint B charges 2% on every transfer
+    let mint_a = create_transfer_fee_mint(&mut program, &payer, &maker, 6, 100);
+    let mint_b = create_transfer_fee_mint(&mut program, &payer, &maker, 6, 200);
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a)
+        .owner(&maker)
+        .token_program_id(&TOKEN_2022_PROGRAM_ID)
+        .send()
+        .unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b)
+        .owner(&taker.pubkey())
+        .token_program_id(&TOKEN_2022_PROGRAM_ID)
+        .send()
+        .unwrap();
+
+    mint_to_token_2022(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000);
+    mint_to_token_2022(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000);
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &707u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address_with_program_id(&escrow, &mint_a, &TOKEN_2022_PROGRAM_ID);
+
+    let associated_token_program = spl_associated_token_account::ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    // Make: deposit 1000 of Mint A, wants 500 of Mint B
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker, mint_a, mint_b, maker_ata_a: Some(maker_ata_a), escrow, vault, associated_token_program,
+            token_program_a: TOKEN_2022_PROGRAM_ID, token_program_b: TOKEN_2022_PROGRAM_ID, system_program,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Make { deposit: 1000, seed: 707u64, receive: 500, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    // The vault only received 990 after the 1% fee, and the escrow records that
+    assert_eq!(get_token_amount(&program, &vault), 990, "Vault should hold the deposit net of the transfer fee");
+
+    let escrow_account = program.get_account(&escrow).unwrap();
+    let escrow_data = anchor_escrow::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+    assert_eq!(escrow_data.deposit, 990, "Escrow should record the amount actually received");
+
+    let taker_ata_a = associated_token::get_associated_token_address_with_program_id(&taker.pubkey(), &mint_a, &TOKEN_2022_PROGRAM_ID);
+    let maker_ata_b = associated_token::get_associated_token_address_with_program_id(&maker, &mint_b, &TOKEN_2022_PROGRAM_ID);
+
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b), maker_ata_b: Some(maker_ata_b), escrow, vault, associated_token_program,
+            token_program_a: TOKEN_2022_PROGRAM_ID, token_program_b: TOKEN_2022_PROGRAM_ID, system_program,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    let tx = program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Take transaction successful with transfer fee mints");
+    msg!("CUs Consumed: {}", tx.compute_units_consumed);
+
+    // The maker is credited exactly 500 net; the taker paid the fee on top
+    assert_eq!(get_token_amount(&program, &maker_ata_b), 500, "Maker should receive exactly 500 tokens of Mint B net");
+    let taker_paid = 1000000000 - get_token_amount(&program, &taker_ata_b);
+    assert!(taker_paid > 500, "Taker should pay the Mint B transfer fee on top, paid {}", taker_paid);
+
+    // The taker gets the vault balance minus the 1% fee on the way out (990 - 10)
+    assert_eq!(get_token_amount(&program, &taker_ata_a), 980, "Taker should receive the vault net of the transfer fee");
+
+    // The vault held withheld fees but was still closed
+    assert_eq!(program.get_account(&vault).map_or(0, |acc| acc.lamports), 0, "Vault should be closed");
+    assert_eq!(program.get_account(&escrow).map_or(0, |acc| acc.lamports), 0, "Escrow should be closed");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_with_transfer_hook_mint() {
+    // Setup
+    let (mut program, payer) = setup();
+    load_transfer_hook(&mut program);
+
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    // Mint A calls the test hook on every transfer, Mint B is a plain SPL mint
+    let mint_a = create_transfer_hook_mint(&mut program, &payer, &maker, 6);
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    // Register the hook's extra accounts (a per-mint transfer counter)
+    let extra_account_meta_list = Pubkey::find_program_address(&[b"extra-account-metas", mint_a.as_ref()], &transfer_hook::ID).0;
+    let counter = Pubkey::find_program_address(&[b"counter", mint_a.as_ref()], &transfer_hook::ID).0;
+
+    let init_hook_ix = Instruction {
+        program_id: transfer_hook::ID,
+        accounts: transfer_hook::accounts::InitializeExtraAccountMetaList {
+            payer: payer.pubkey(), extra_account_meta_list, mint: mint_a, counter, system_program: SYSTEM_PROGRAM_ID,
+        }.to_account_metas(None),
+        data: transfer_hook::instruction::InitializeExtraAccountMetaList {}.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[init_hook_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    // Extra accounts the escrow forwards to every transfer of Mint A
+    let hook_accounts = vec![
+        AccountMeta::new(counter, false),
+        AccountMeta::new_readonly(transfer_hook::ID, false),
+        AccountMeta::new_readonly(extra_account_meta_list, false),
+    ];
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a)
+        .owner(&maker)
+        .token_program_id(&TOKEN_2022_PROGRAM_ID)
+        .send()
+        .unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b).owner(&taker.pubkey()).send().unwrap();
+
+    mint_to_token_2022(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000);
+    MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000).send().unwrap();
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &808u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address_with_program_id(&escrow, &mint_a, &TOKEN_2022_PROGRAM_ID);
+
+    let associated_token_program = spl_associated_token_account::ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    let mut make_accounts = anchor_escrow::accounts::Make {
+        maker, mint_a, mint_b, maker_ata_a: Some(maker_ata_a), escrow, vault, associated_token_program,
+        token_program_a: TOKEN_2022_PROGRAM_ID, token_program_b: TOKEN_PROGRAM_ID, system_program,
+        config: config_address(),
+        mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+    }.to_account_metas(None);
+    make_accounts.extend(hook_accounts.clone());
+
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: make_accounts,
+        data: anchor_escrow::instruction::Make { deposit: 10, seed: 808u64, receive: 20, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Make transaction successful with transfer hook mint");
+
+    let taker_ata_a = associated_token::get_associated_token_address_with_program_id(&taker.pubkey(), &mint_a, &TOKEN_2022_PROGRAM_ID);
+    let maker_ata_b = associated_token::get_associated_token_address_with_program_id(&maker, &mint_b, &TOKEN_PROGRAM_ID);
+
+    let mut take_accounts = anchor_escrow::accounts::Take {
+        taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b), maker_ata_b: Some(maker_ata_b), escrow, vault, associated_token_program,
+        token_program_a: TOKEN_2022_PROGRAM_ID, token_program_b: TOKEN_PROGRAM_ID, system_program,
+        config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+    }.to_account_metas(None);
+    take_accounts.extend(hook_accounts);
+
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: take_accounts,
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    let tx = program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Take transaction successful with transfer hook mint");
+    msg!("CUs Consumed: {}", tx.compute_units_consumed);
+
+    assert_eq!(get_token_amount(&program, &taker_ata_a), 10, "Taker should have received 10 tokens of Mint A");
+    assert_eq!(get_token_amount(&program, &maker_ata_b), 20, "Maker should have received 20 tokens of Mint B");
+
+    // The hook ran for the deposit into the vault and the withdrawal to the taker
+    let counter_account = program.get_account(&counter).unwrap();
+    let counter_data = transfer_hook::Counter::try_deserialize(&mut counter_account.data.as_ref()).unwrap();
+    assert_eq!(counter_data.transfers, 2, "Transfer hook should have run for both Mint A transfers");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_with_native_sol_deposit() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    // Maker deposits plain lamports, no wSOL account needed
+    let mint_a = spl_token::native_mint::ID;
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b).owner(&taker.pubkey()).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000).send().unwrap();
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &808u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+
+    let associated_token_program = spl_associated_token_account::ID;
+    let token_program = TOKEN_PROGRAM_ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker, mint_a, mint_b, maker_ata_a: None, escrow, vault, associated_token_program,
+            token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Make { deposit: LAMPORTS_PER_SOL, seed: 808u64, receive: 20, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Make transaction successful with native SOL deposit");
+
+    assert_eq!(get_token_amount(&program, &vault), LAMPORTS_PER_SOL, "Vault should hold the wrapped SOL deposit");
+
+    let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+    let taker_lamports_before = program.get_balance(&taker.pubkey()).unwrap();
+
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b), maker_ata_b: Some(maker_ata_b), escrow, vault, associated_token_program,
+            token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Take transaction successful, taker paid out in lamports");
+
+    // Taker also funds and is refunded the temporary wSOL account rent, so only the
+    // transaction fee and the maker ATA B rent come out of the deposit
+    let maker_ata_b_rent = program.get_balance(&maker_ata_b).unwrap();
+    let taker_lamports_after = program.get_balance(&taker.pubkey()).unwrap();
+    assert_eq!(taker_lamports_after, taker_lamports_before + LAMPORTS_PER_SOL - maker_ata_b_rent - 5000, "Taker should have received the deposit as lamports");
+    assert!(program.get_account(&taker_ata_a).is_none_or(|acc| acc.lamports == 0), "Taker wSOL account should be closed");
+    assert_eq!(get_token_amount(&program, &maker_ata_b), 20, "Maker should have received 20 tokens of Mint B");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_paying_native_sol() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    // Maker asks for native SOL, taker pays without wrapping
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = spl_token::native_mint::ID;
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &909u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+
+    let associated_token_program = spl_associated_token_account::ID;
+    let token_program = TOKEN_PROGRAM_ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker, mint_a, mint_b, maker_ata_a: Some(maker_ata_a), escrow, vault, associated_token_program,
+            token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Make { deposit: 10, seed: 909u64, receive: 2 * LAMPORTS_PER_SOL, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Make transaction successful, asking for native SOL");
+
+    let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+
+    // Escrow and vault rent are returned to the maker on the final fill
+    let maker_lamports_before = program.get_balance(&maker).unwrap()
+        + program.get_balance(&escrow).unwrap()
+        + program.get_balance(&vault).unwrap();
+
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: None, maker_ata_b: None, escrow, vault, associated_token_program,
+            token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Take transaction successful, maker paid in lamports");
+
+    let maker_lamports_after = program.get_balance(&maker).unwrap();
+    assert_eq!(maker_lamports_after, maker_lamports_before + 2 * LAMPORTS_PER_SOL, "Maker should have received 2 SOL");
+    assert_eq!(get_token_amount(&program, &taker_ata_a), 10, "Taker should have received 10 tokens of Mint A");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_make_rejects_degenerate_params() {
+    let mut env = EscrowTestEnv::new().with_mints(6);
+    let (mint_a, mint_b) = (env.mint_a, env.mint_b);
+
+    // Created at a non-zero slot, so start_time + i64::MAX cannot be represented
+    env.warp(100);
+
+    let make_args = |seed: u64, deposit: u64, receive: u64, lock_period: i64, commit_period: i64| anchor_escrow::instruction::Make {
+        deposit, seed, receive, lock_kind: LockKind::Slot, lock_period, commit_period, expires_at: None, taker: None, nft: None, auction: None,
+    };
+
+    // (case, make args, mint B, expected error)
+    let cases = [
+        ("nothing to trade", make_args(1001, 0, 10, 0, 0), mint_b, ("ZeroDeposit", 6008)),
+        ("asking for nothing in return", make_args(1002, 10, 0, 0, 0), mint_b, ("ZeroReceive", 6009)),
+        ("a negative lock period", make_args(1003, 10, 10, -5, 0), mint_b, ("NegativeLock", 6010)),
+        ("a negative commit period", make_args(1004, 10, 10, 0, -5), mint_b, ("NegativeLock", 6010)),
+        ("a mint traded for itself", make_args(1005, 10, 10, 0, 0), mint_a, ("SameMint", 6011)),
+        ("a lock that never ends", make_args(1006, 10, 10, i64::MAX, 0), mint_b, ("Overflow", 6012)),
+        ("a commit period that never ends", make_args(1007, 10, 10, 0, i64::MAX), mint_b, ("Overflow", 6012)),
+    ];
+
+    for (case, args, case_mint_b, (error, code)) in cases {
+        env.mint_b = case_mint_b;
+
+        let result = env.make_with(args);
+
+        assert!(result.is_err(), "Make should reject {case}");
+        let error_msg = format!("{:?}", result.unwrap_err());
+        assert!(error_msg.contains(&format!("{code:#x}")) || error_msg.contains(&code.to_string()),
+            "Error for {case} should be {error} ({code}/{code:#x}), got: {}", error_msg);
+        assert!(!env.exists(&env.escrow()), "No escrow should be created for {case}");
+    }
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_update() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b).owner(&taker.pubkey()).send().unwrap();
+
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000).send().unwrap();
+
+    send_make(&mut program, &payer, mint_a, mint_b, anchor_escrow::instruction::Make {
+        deposit: 100, seed: 1201u64, receive: 50, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: Some(1000), taker: None, nft: None, auction: None,
+    }).unwrap();
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &1201u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+
+    let associated_token_program = spl_associated_token_account::ID;
+    let token_program = TOKEN_PROGRAM_ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    let update_accounts = anchor_escrow::accounts::Update {
+        maker, mint_a, maker_ata_a: Some(maker_ata_a), escrow, vault, associated_token_program, token_program, system_program,
+    };
+
+    // Reprice, top up the vault and push expiry out in one instruction
+    let update_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: update_accounts.to_account_metas(None),
+        data: anchor_escrow::instruction::Update { receive: Some(80), deposit_change: Some(DepositChange::TopUp(60)), expires_at: Some(2000) }.data(),
+    };
+
+    let tx = program.send_transaction(Transaction::new(&[&payer], Message::new(&[update_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Update transaction successful");
+
+    let escrow_account = program.get_account(&escrow).unwrap();
+    let escrow_data = anchor_escrow::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+    assert_eq!(escrow_data.deposit, 160);
+    assert_eq!(escrow_data.receive, 80);
+    assert_eq!(escrow_data.expires_at, Some(2000));
+    assert_eq!(get_token_amount(&program, &vault), 160, "Vault should hold the topped up deposit");
+
+    let event = find_event::<EscrowUpdated>(&tx.logs).expect("EscrowUpdated event should be emitted");
+    assert_eq!(event.deposit, 160);
+    assert_eq!(event.receive, 80);
+    assert_eq!(event.expires_at, Some(2000));
+
+    // Withdraw part of the deposit again
+    let update_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: update_accounts.to_account_metas(None),
+        data: anchor_escrow::instruction::Update { receive: None, deposit_change: Some(DepositChange::Withdraw(40)), expires_at: None }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[update_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    assert_eq!(get_token_amount(&program, &vault), 120, "Vault should hold 120 tokens after the withdrawal");
+    assert_eq!(get_token_amount(&program, &maker_ata_a), 1000000000 - 120, "Maker should have the withdrawn tokens back");
+
+    // Past the original expiry the escrow is still takeable at the new price
+    program.warp_to_slot(1500);
+
+    let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b),
+            maker_ata_b: Some(maker_ata_b), escrow, vault, associated_token_program, token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash())).unwrap();
+
+    assert_eq!(get_token_amount(&program, &taker_ata_a), 120, "Taker should have received the updated deposit");
+    assert_eq!(get_token_amount(&program, &maker_ata_b), 80, "Maker should have received the updated price");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_update_withdraws_native_sol_deposit() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+
+    // Maker deposits plain lamports and never holds a wSOL account
+    let mint_a = spl_token::native_mint::ID;
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &1204u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+    let maker_ata_a = associated_token::get_associated_token_address(&maker, &mint_a);
+
+    let associated_token_program = spl_associated_token_account::ID;
+    let token_program = TOKEN_PROGRAM_ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker, mint_a, mint_b, maker_ata_a: None, escrow, vault, associated_token_program,
+            token_program_a: token_program, token_program_b: token_program, system_program,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Make { deposit: LAMPORTS_PER_SOL, seed: 1204u64, receive: 20, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix], Some(&maker)), program.latest_blockhash())).unwrap();
+
+    // The withdrawal is unwrapped through the maker's wSOL account, created and closed on the way
+    let update_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Update {
+            maker, mint_a, maker_ata_a: Some(maker_ata_a), escrow, vault, associated_token_program, token_program, system_program,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Update { receive: None, deposit_change: Some(DepositChange::Withdraw(LAMPORTS_PER_SOL / 4)), expires_at: None }.data(),
+    };
+
+    let maker_lamports_before = program.get_balance(&maker).unwrap();
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[update_ix], Some(&maker)), program.latest_blockhash())).unwrap();
+
+    msg!("Withdrew part of a native SOL deposit");
+
+    let maker_lamports_after = program.get_balance(&maker).unwrap();
+    assert_eq!(maker_lamports_after, maker_lamports_before + LAMPORTS_PER_SOL / 4 - 5000, "Maker should have received the withdrawal as lamports");
+    assert!(program.get_account(&maker_ata_a).is_none_or(|acc| acc.lamports == 0), "Maker wSOL account should be closed");
+    assert_eq!(get_token_amount(&program, &vault), LAMPORTS_PER_SOL * 3 / 4, "Vault should hold the rest of the deposit");
+
+    let escrow_data = anchor_escrow::state::Escrow::try_deserialize(&mut program.get_account(&escrow).unwrap().data.as_ref()).unwrap();
+    assert_eq!(escrow_data.deposit, LAMPORTS_PER_SOL * 3 / 4);
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_price_limits() {
+    // Setup: 100 of Mint A for 50 of Mint B, committed for 10 slots
+    let mut env = EscrowTestEnv::new().with_mints(6);
+    let taker = env.new_taker();
+
+    env.make_with(anchor_escrow::instruction::Make {
+        deposit: 100, seed: 1203, receive: 50, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 10, expires_at: None, taker: None, nft: None, auction: None,
+    }).unwrap();
+
+    // Raising the price during the commit period (should fail with ReceiveLocked)
+    let result = env.update(anchor_escrow::instruction::Update { receive: Some(80), deposit_change: None, expires_at: None });
+    let error_msg = format!("{:?}", result.expect_err("Price increase should be rejected while committed"));
+    assert!(error_msg.contains("0x178c") || error_msg.contains("6028"),
+        "Error should be ReceiveLocked (6028/0x178c), got: {}", error_msg);
+
+    // Lowering it is always allowed
+    env.update(anchor_escrow::instruction::Update { receive: Some(40), deposit_change: None, expires_at: None }).unwrap();
+
+    // Once committed funds may leave, the maker can raise it again
+    env.warp(10);
+    env.update(anchor_escrow::instruction::Update { receive: Some(60), deposit_change: None, expires_at: None }).unwrap();
+
+    // A taker who saw the old price of 40 is protected (should fail with SlippageExceeded)
+    let result = env.take_with(&taker, anchor_escrow::instruction::Take { max_amount_b: 40, referrer_fee_bps: 0 });
+    let error_msg = format!("{:?}", result.expect_err("Take above the taker's limit should be rejected"));
+    assert!(error_msg.contains("0x178d") || error_msg.contains("6029"),
+        "Error should be SlippageExceeded (6029/0x178d), got: {}", error_msg);
+
+    // 20 of Mint B buys 33 of Mint A at the new price, not the 50 it bought before
+    let result = env.take_partial_with(&taker, anchor_escrow::instruction::TakePartial { amount_b: 20, min_amount_a: 50, referrer_fee_bps: 0 });
+    let error_msg = format!("{:?}", result.expect_err("Partial fill below the taker's limit should be rejected"));
+    assert!(error_msg.contains("0x178d") || error_msg.contains("6029"),
+        "Error should be SlippageExceeded (6029/0x178d), got: {}", error_msg);
+
+    env.take_partial_with(&taker, anchor_escrow::instruction::TakePartial { amount_b: 20, min_amount_a: 33, referrer_fee_bps: 0 }).unwrap();
+    assert_eq!(env.balance_a(&taker.pubkey()), 33, "Taker should have received 33 tokens of Mint A");
+
+    env.take_with(&taker, anchor_escrow::instruction::Take { max_amount_b: 40, referrer_fee_bps: 0 }).unwrap();
+    assert!(!env.exists(&env.escrow()), "Escrow should be closed after the final fill");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_update_rejects_invalid_changes() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let intruder = Keypair::new();
+
+    program.airdrop(&intruder.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+
+    send_make(&mut program, &payer, mint_a, mint_b, anchor_escrow::instruction::Make {
+        deposit: 100, seed: 1202u64, receive: 50, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: Some(1000), taker: None, nft: None, auction: None,
+    }).unwrap();
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &1202u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+
+    let update_accounts = anchor_escrow::accounts::Update {
+        maker, mint_a, maker_ata_a: Some(maker_ata_a), escrow, vault,
+        associated_token_program: spl_associated_token_account::ID, token_program: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+    };
+
+    let cases = [
+        // Shortening expiry (should fail with ExpiryNotExtended)
+        (anchor_escrow::instruction::Update { receive: None, deposit_change: None, expires_at: Some(500) }, "0x177d", "6013"),
+        // Withdrawing the whole deposit (should fail with InvalidWithdrawAmount, use refund instead)
+        (anchor_escrow::instruction::Update { receive: None, deposit_change: Some(DepositChange::Withdraw(100)), expires_at: None }, "0x177e", "6014"),
+        // Repricing to zero (should fail with ZeroReceive)
+        (anchor_escrow::instruction::Update { receive: Some(0), deposit_change: None, expires_at: None }, "0x1779", "6009"),
+    ];
+
+    for (args, code_hex, code_dec) in cases {
+        let update_ix = Instruction { program_id: PROGRAM_ID, accounts: update_accounts.to_account_metas(None), data: args.data() };
+        let result = program.send_transaction(Transaction::new(&[&payer], Message::new(&[update_ix], Some(&payer.pubkey())), program.latest_blockhash()));
+
+        assert!(result.is_err(), "Update should be rejected");
+        let error_msg = format!("{:?}", result.unwrap_err());
+        assert!(error_msg.contains(code_hex) || error_msg.contains(code_dec),
+            "Error should be {}/{}, got: {}", code_dec, code_hex, error_msg);
+    }
+
+    // Someone other than the maker cannot reprice the escrow
+    let update_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Update { maker: intruder.pubkey(), maker_ata_a: None, ..update_accounts }.to_account_metas(None),
+        data: anchor_escrow::instruction::Update { receive: Some(1), deposit_change: None, expires_at: None }.data(),
+    };
+
+    let result = program.send_transaction(Transaction::new(&[&intruder], Message::new(&[update_ix], Some(&intruder.pubkey())), program.latest_blockhash()));
+    assert!(result.is_err(), "Only the maker should be able to update the escrow");
+
+    let escrow_account = program.get_account(&escrow).unwrap();
+    let escrow_data = anchor_escrow::state::Escrow::try_deserialize(&mut escrow_account.data.as_ref()).unwrap();
+    assert_eq!(escrow_data.receive, 50, "Escrow terms should be untouched");
+    assert_eq!(escrow_data.deposit, 100);
+    assert_eq!(escrow_data.expires_at, Some(1000));
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_with_protocol_fee() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+    let treasury = Keypair::new().pubkey();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    // Admin turns on a 2.5% protocol fee paid to a dedicated treasury
+    let update_config_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::UpdateConfig { admin: payer.pubkey(), config: config_address() }.to_account_metas(None),
+        data: anchor_escrow::instruction::UpdateConfig { new_admin: None, fee_bps: Some(250), max_referrer_fee_bps: None, treasury: Some(treasury) }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[update_config_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    let config_account = program.get_account(&config_address()).unwrap();
+    let config_data = anchor_escrow::state::Config::try_deserialize(&mut config_account.data.as_ref()).unwrap();
+    assert_eq!(config_data.fee_bps, 250);
+    assert_eq!(config_data.treasury, treasury);
+    assert_eq!(config_data.admin, payer.pubkey());
+
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b).owner(&taker.pubkey()).send().unwrap();
+
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000).send().unwrap();
+
+    send_make(&mut program, &payer, mint_a, mint_b, anchor_escrow::instruction::Make {
+        deposit: 100, seed: 1301u64, receive: 1000, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None,
+    }).unwrap();
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &1301u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+    let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+    let treasury_ata_b = associated_token::get_associated_token_address(&treasury, &mint_b);
+
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b),
+            maker_ata_b: Some(maker_ata_b), config: config_address(), treasury, treasury_ata_b: Some(treasury_ata_b), referrer_ata_b: None, escrow, vault,
+            associated_token_program: spl_associated_token_account::ID, token_program_a: TOKEN_PROGRAM_ID, token_program_b: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    let tx = program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Take transaction successful with protocol fee");
+
+    assert_eq!(get_token_amount(&program, &taker_ata_b), 1000000000 - 1000, "Taker should have paid exactly the asking price");
+    assert_eq!(get_token_amount(&program, &maker_ata_b), 975, "Maker should receive the price minus the protocol fee");
+    assert_eq!(get_token_amount(&program, &treasury_ata_b), 25, "Treasury should receive the 2.5% protocol fee");
+    assert_eq!(get_token_amount(&program, &taker_ata_a), 100, "Taker should have received the whole deposit");
+
+    let event = find_event::<EscrowTaken>(&tx.logs).expect("EscrowTaken event should be emitted");
+    assert_eq!(event.amount_b, 1000);
+    assert_eq!(event.protocol_fee, 25);
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_update_config_requires_admin() {
+    // Setup
+    let (mut program, payer) = setup();
+    let intruder = Keypair::new();
+
+    program.airdrop(&intruder.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    // Someone other than the admin cannot change the fee (should fail with has_one)
+    let update_config_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::UpdateConfig { admin: intruder.pubkey(), config: config_address() }.to_account_metas(None),
+        data: anchor_escrow::instruction::UpdateConfig { new_admin: None, fee_bps: Some(10_000), max_referrer_fee_bps: None, treasury: Some(intruder.pubkey()) }.data(),
+    };
+
+    let result = program.send_transaction(Transaction::new(&[&intruder], Message::new(&[update_config_ix], Some(&intruder.pubkey())), program.latest_blockhash()));
+    assert!(result.is_err(), "Only the admin should be able to update the config");
+
+    // A protocol fee that leaves no room for the referrer cap is rejected (should fail with InvalidFeeBps)
+    let update_config_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::UpdateConfig { admin: payer.pubkey(), config: config_address() }.to_account_metas(None),
+        data: anchor_escrow::instruction::UpdateConfig { new_admin: None, fee_bps: Some(9_950), max_referrer_fee_bps: None, treasury: None }.data(),
+    };
+
+    let result = program.send_transaction(Transaction::new(&[&payer], Message::new(&[update_config_ix], Some(&payer.pubkey())), program.latest_blockhash()));
+
+    assert!(result.is_err(), "Fee plus referrer cap above 10000 bps should be rejected");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x177f") || error_msg.contains("6015"),
+        "Error should be InvalidFeeBps (6015/0x177f), got: {}", error_msg);
+
+    // Fees above 100% are rejected (should fail with InvalidFeeBps)
+    let update_config_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::UpdateConfig { admin: payer.pubkey(), config: config_address() }.to_account_metas(None),
+        data: anchor_escrow::instruction::UpdateConfig { new_admin: None, fee_bps: Some(10_001), max_referrer_fee_bps: None, treasury: None }.data(),
+    };
+
+    let result = program.send_transaction(Transaction::new(&[&payer], Message::new(&[update_config_ix], Some(&payer.pubkey())), program.latest_blockhash()));
+
+    assert!(result.is_err(), "Fee above 10000 bps should be rejected");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x177f") || error_msg.contains("6015"),
+        "Error should be InvalidFeeBps (6015/0x177f), got: {}", error_msg);
+
+    // The config can only be initialized once, even by the upgrade authority
+    let initialize_config_ix = initialize_config_ix(&payer.pubkey(), 10_000, 0, intruder.pubkey());
+
+    let result = program.send_transaction(Transaction::new(&[&payer], Message::new(&[initialize_config_ix], Some(&payer.pubkey())), program.latest_blockhash()));
+    assert!(result.is_err(), "Config should not be re-initialized");
+
+    let config_account = program.get_account(&config_address()).unwrap();
+    let config_data = anchor_escrow::state::Config::try_deserialize(&mut config_account.data.as_ref()).unwrap();
+    assert_eq!(config_data.admin, payer.pubkey(), "Admin should be unchanged");
+    assert_eq!(config_data.fee_bps, 0, "Fee should be unchanged");
+    assert_eq!(config_data.treasury, payer.pubkey(), "Treasury should be unchanged");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_initialize_config_requires_upgrade_authority() {
+    // Setup: deployed, but nobody has initialized the config yet
+    let (mut program, payer) = deploy();
+    let intruder = Keypair::new();
+
+    program.airdrop(&intruder.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    // Front-running the deployer with a 100% fee to their own treasury (should fail with NotUpgradeAuthority)
+    let front_run_ix = initialize_config_ix(&intruder.pubkey(), 10_000, 0, intruder.pubkey());
+
+    let result = program.send_transaction(Transaction::new(&[&intruder], Message::new(&[front_run_ix], Some(&intruder.pubkey())), program.latest_blockhash()));
+
+    assert!(result.is_err(), "Only the upgrade authority should be able to initialize the config");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x178e") || error_msg.contains("6030"),
+        "Error should be NotUpgradeAuthority (6030/0x178e), got: {}", error_msg);
+    assert!(program.get_account(&config_address()).is_none_or(|account| account.lamports == 0), "Config should not exist");
+
+    // The upgrade authority can still claim it
+    let initialize_config_ix = initialize_config_ix(&payer.pubkey(), 0, 100, payer.pubkey());
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[initialize_config_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    let config_account = program.get_account(&config_address()).unwrap();
+    let config_data = anchor_escrow::state::Config::try_deserialize(&mut config_account.data.as_ref()).unwrap();
+    assert_eq!(config_data.admin, payer.pubkey(), "Upgrade authority should be the admin");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_with_referrer_fee() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+    let referrer = Keypair::new();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+    program.airdrop(&referrer.pubkey(), LAMPORTS_PER_SOL).unwrap();
+
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b).owner(&taker.pubkey()).send().unwrap();
+    let referrer_ata_b = CreateAssociatedTokenAccount::new(&mut program, &referrer, &mint_b).owner(&referrer.pubkey()).send().unwrap();
+
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000).send().unwrap();
+
+    send_make(&mut program, &payer, mint_a, mint_b, anchor_escrow::instruction::Make {
+        deposit: 100, seed: 1401u64, receive: 1000, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None,
+    }).unwrap();
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &1401u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+    let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+
+    let take_accounts = anchor_escrow::accounts::Take {
+        taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b),
+        maker_ata_b: Some(maker_ata_b), config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: Some(referrer_ata_b),
+        escrow, vault, associated_token_program: spl_associated_token_account::ID, token_program_a: TOKEN_PROGRAM_ID, token_program_b: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+    };
+
+    // Asking for more than the configured 1% cap (should fail with ReferrerFeeTooHigh)
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: take_accounts.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 101 }.data(),
+    };
+
+    let result = program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash()));
+
+    assert!(result.is_err(), "Take should fail when the referrer fee exceeds the cap");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1780") || error_msg.contains("6016"),
+        "Error should be ReferrerFeeTooHigh (6016/0x1780), got: {}", error_msg);
+
+    // Exactly at the cap is fine
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: take_accounts.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 100 }.data(),
+    };
+
+    let tx = program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Take transaction successful with referrer fee");
+
+    assert_eq!(get_token_amount(&program, &taker_ata_b), 1000000000 - 1010, "Taker should have paid the asking price plus the referrer fee");
+    assert_eq!(get_token_amount(&program, &referrer_ata_b), 10, "Referrer should receive 1% of the payment");
+    assert_eq!(get_token_amount(&program, &maker_ata_b), 1000, "Maker should receive the full asking price");
+
+    let event = find_event::<EscrowTaken>(&tx.logs).expect("EscrowTaken event should be emitted");
+    assert_eq!(event.referrer, Some(referrer.pubkey()));
+    assert_eq!(event.referrer_fee, 10);
+    assert_eq!(event.protocol_fee, 0);
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_pause_blocks_make_and_take_but_not_refund() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b).owner(&taker.pubkey()).send().unwrap();
+
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000).send().unwrap();
+
+    // Escrow opened before the emergency
+    send_make(&mut program, &payer, mint_a, mint_b, anchor_escrow::instruction::Make {
+        deposit: 100, seed: 1501u64, receive: 50, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None,
+    }).unwrap();
+
+    // Only the admin can pull the circuit breaker
+    let pause_ix = |admin: Pubkey, paused: bool| Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::UpdateConfig { admin, config: config_address() }.to_account_metas(None),
+        data: anchor_escrow::instruction::SetPaused { paused }.data(),
+    };
+
+    let result = program.send_transaction(Transaction::new(&[&taker], Message::new(&[pause_ix(taker.pubkey(), true)], Some(&taker.pubkey())), program.latest_blockhash()));
+    assert!(result.is_err(), "Only the admin should be able to pause");
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[pause_ix(payer.pubkey(), true)], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Program paused");
+
+    // New escrows are rejected (should fail with Paused)
+    let result = send_make(&mut program, &payer, mint_a, mint_b, anchor_escrow::instruction::Make {
+        deposit: 100, seed: 1502u64, receive: 50, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None,
+    });
+
+    assert!(result.is_err(), "Make should fail while paused");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1781") || error_msg.contains("6017"),
+        "Error should be Paused (6017/0x1781), got: {}", error_msg);
+
+    // Open escrows cannot be taken (should fail with Paused)
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &1501u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+    let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b),
+            maker_ata_b: Some(maker_ata_b), config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+            escrow, vault, associated_token_program: spl_associated_token_account::ID, token_program_a: TOKEN_PROGRAM_ID, token_program_b: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    let result = program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash()));
+
+    assert!(result.is_err(), "Take should fail while paused");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1781") || error_msg.contains("6017"),
+        "Error should be Paused (6017/0x1781), got: {}", error_msg);
+
+    // Makers can always get their funds out
+    let refund_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Refund {
+            maker, mint_a, maker_ata_a: Some(maker_ata_a), escrow, vault, token_program: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Refund {}.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[refund_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Refund successful while paused");
+
+    assert_eq!(get_token_amount(&program, &maker_ata_a), 1000000000, "Maker should have all tokens back");
+
+    // Unpausing reopens make, retried with a fresh blockhash
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[pause_ix(payer.pubkey(), false)], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+    program.expire_blockhash();
+
+    send_make(&mut program, &payer, mint_a, mint_b, anchor_escrow::instruction::Make {
+        deposit: 100, seed: 1502u64, receive: 50, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None,
+    }).unwrap();
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_make_mint_registry() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let intruder = Keypair::new();
+
+    program.airdrop(&intruder.pubkey(), LAMPORTS_PER_SOL).unwrap();
+
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+
+    let make_args = |seed: u64| anchor_escrow::instruction::Make {
+        deposit: 10, seed, receive: 10, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None,
+    };
+
+    // Only the admin manages the registry
+    let set_mint_status_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::SetMintStatus {
+            admin: intruder.pubkey(), config: config_address(), mint_entry: mint_entry_address(&mint_b), system_program: SYSTEM_PROGRAM_ID,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::SetMintStatus { mint: mint_b, status: MintStatus::Allowed }.data(),
+    };
+
+    let result = program.send_transaction(Transaction::new(&[&intruder], Message::new(&[set_mint_status_ix], Some(&intruder.pubkey())), program.latest_blockhash()));
+    assert!(result.is_err(), "Only the admin should be able to register mints");
+
+    // A denied mint is rejected on either side (should fail with MintNotAllowed)
+    set_mint_status(&mut program, &payer, mint_b, MintStatus::Denied);
+
+    let result = send_make(&mut program, &payer, mint_a, mint_b, make_args(1601));
+    assert!(result.is_err(), "Make should fail for a denied mint");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1782") || error_msg.contains("6018"),
+        "Error should be MintNotAllowed (6018/0x1782), got: {}", error_msg);
+
+    // Under allowlist-only, unregistered mints are rejected too
+    set_mint_policy(&mut program, &payer, true, false);
+    set_mint_status(&mut program, &payer, mint_b, MintStatus::Allowed);
+
+    let result = send_make(&mut program, &payer, mint_a, mint_b, make_args(1602));
+    assert!(result.is_err(), "Make should fail for an unregistered mint under allowlist-only");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1782") || error_msg.contains("6018"),
+        "Error should be MintNotAllowed (6018/0x1782), got: {}", error_msg);
+
+    // Once both mints are allowed the escrow is accepted
+    set_mint_status(&mut program, &payer, mint_a, MintStatus::Allowed);
+
+    send_make(&mut program, &payer, mint_a, mint_b, make_args(1603)).unwrap();
+
+    let mint_entry_account = program.get_account(&mint_entry_address(&mint_b)).unwrap();
+    let mint_entry_data = anchor_escrow::state::MintEntry::try_deserialize(&mut mint_entry_account.data.as_ref()).unwrap();
+    assert_eq!(mint_entry_data.mint, mint_b);
+    assert_eq!(mint_entry_data.status, MintStatus::Allowed);
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_make_rejects_risky_mints() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+
+    set_mint_policy(&mut program, &payer, false, true);
+
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+
+    let make_args = |seed: u64| anchor_escrow::instruction::Make {
+        deposit: 10, seed, receive: 10, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None,
+    };
+
+    // A mint whose issuer can freeze the vault (should fail with FreezableMint)
+    let freezable_mint = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).freeze_authority(&maker).send().unwrap();
+
+    let result = send_make(&mut program, &payer, mint_a, freezable_mint, make_args(1611));
+    assert!(result.is_err(), "Make should fail for a freezable mint");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1783") || error_msg.contains("6019"),
+        "Error should be FreezableMint (6019/0x1783), got: {}", error_msg);
+
+    // A Token-2022 mint whose permanent delegate can drain the vault (should fail with RiskyMintExtension)
+    let delegated_mint = create_token_2022_mint_with_extensions(&mut program, &payer, &maker, 6, &[ExtensionType::PermanentDelegate], |mint| vec![
+        spl_token_2022::instruction::initialize_permanent_delegate(&TOKEN_2022_PROGRAM_ID, mint, &maker).unwrap(),
+    ]);
+
+    let result = send_make(&mut program, &payer, mint_a, delegated_mint, make_args(1612));
+    assert!(result.is_err(), "Make should fail for a permanent delegate mint");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1784") || error_msg.contains("6020"),
+        "Error should be RiskyMintExtension (6020/0x1784), got: {}", error_msg);
+
+    // A non-transferable mint would strand the deposit (should fail with RiskyMintExtension)
+    let soulbound_mint = create_token_2022_mint_with_extensions(&mut program, &payer, &maker, 6, &[ExtensionType::NonTransferable], |mint| vec![
+        spl_token_2022::instruction::initialize_non_transferable_mint(&TOKEN_2022_PROGRAM_ID, mint).unwrap(),
+    ]);
+
+    let result = send_make(&mut program, &payer, mint_a, soulbound_mint, make_args(1613));
+    assert!(result.is_err(), "Make should fail for a non-transferable mint");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1784") || error_msg.contains("6020"),
+        "Error should be RiskyMintExtension (6020/0x1784), got: {}", error_msg);
+
+    // Accounts of a default-frozen mint start frozen (should fail with RiskyMintExtension)
+    // Built by hand since Token-2022 insists on a freeze authority for this extension
+    let frozen_mint = Keypair::new();
+    let space = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(&[ExtensionType::DefaultAccountState]).unwrap();
+    let ixs = [
+        solana_system_interface::instruction::create_account(
+            &payer.pubkey(), &frozen_mint.pubkey(), program.minimum_balance_for_rent_exemption(space), space as u64, &TOKEN_2022_PROGRAM_ID,
+        ),
+        spl_token_2022::extension::default_account_state::instruction::initialize_default_account_state(
+            &TOKEN_2022_PROGRAM_ID, &frozen_mint.pubkey(), &spl_token_2022::state::AccountState::Frozen,
+        ).unwrap(),
+        spl_token_2022::instruction::initialize_mint2(&TOKEN_2022_PROGRAM_ID, &frozen_mint.pubkey(), &maker, Some(&maker), 6).unwrap(),
+    ];
+    program.send_transaction(Transaction::new(&[&payer, &frozen_mint], Message::new(&ixs, Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+    let frozen_mint = frozen_mint.pubkey();
+
+    let result = send_make(&mut program, &payer, mint_a, frozen_mint, make_args(1614));
+    assert!(result.is_err(), "Make should fail for a default-frozen mint");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1784") || error_msg.contains("6020"),
+        "Error should be RiskyMintExtension (6020/0x1784), got: {}", error_msg);
+
+    // An explicitly allowed mint skips the automatic checks
+    set_mint_status(&mut program, &payer, freezable_mint, MintStatus::Allowed);
+
+    send_make(&mut program, &payer, mint_a, freezable_mint, make_args(1615)).unwrap();
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_basket() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    // Maker offers a legacy and a Token-2022 mint, and asks for two legacy mints
+    let deposit_x = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let deposit_y = create_token_2022_mint(&mut program, &payer, &maker, 9);
+    let request_x = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let request_y = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_x = CreateAssociatedTokenAccount::new(&mut program, &payer, &deposit_x).owner(&maker).send().unwrap();
+    let maker_ata_y = CreateAssociatedTokenAccount::new(&mut program, &payer, &deposit_y).owner(&maker).token_program_id(&TOKEN_2022_PROGRAM_ID).send().unwrap();
+    let taker_request_ata_x = CreateAssociatedTokenAccount::new(&mut program, &taker, &request_x).owner(&taker.pubkey()).send().unwrap();
+    let taker_request_ata_y = CreateAssociatedTokenAccount::new(&mut program, &taker, &request_y).owner(&taker.pubkey()).send().unwrap();
+
+    MintTo::new(&mut program, &payer, &deposit_x, &maker_ata_x, 1000000000).send().unwrap();
+    mint_to_token_2022(&mut program, &payer, &deposit_y, &maker_ata_y, 1000000000);
+    MintTo::new(&mut program, &payer, &request_x, &taker_request_ata_x, 1000000000).send().unwrap();
+    MintTo::new(&mut program, &payer, &request_y, &taker_request_ata_y, 1000000000).send().unwrap();
+
+    let basket = Pubkey::find_program_address(&[b"basket", maker.as_ref(), &1701u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault_x = associated_token::get_associated_token_address(&basket, &deposit_x);
+    let vault_y = associated_token::get_associated_token_address_with_program_id(&basket, &deposit_y, &TOKEN_2022_PROGRAM_ID);
+
+    let deposits = vec![BasketLeg { mint: deposit_x, amount: 100 }, BasketLeg { mint: deposit_y, amount: 200 }];
+    let requests = vec![BasketLeg { mint: request_x, amount: 30 }, BasketLeg { mint: request_y, amount: 40 }];
+
+    // Deposit legs: mint, registry entry, maker ATA, vault, token program. Request legs: mint, registry entry
+    let mut make_accounts = anchor_escrow::accounts::MakeBasket {
+        maker, basket, config: config_address(), associated_token_program: spl_associated_token_account::ID, system_program: SYSTEM_PROGRAM_ID,
+    }.to_account_metas(None);
+    for (mint, maker_ata, vault, token_program) in [(deposit_x, maker_ata_x, vault_x, TOKEN_PROGRAM_ID), (deposit_y, maker_ata_y, vault_y, TOKEN_2022_PROGRAM_ID)] {
+        make_accounts.extend([
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new_readonly(mint_entry_address(&mint), false),
+            AccountMeta::new(maker_ata, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new_readonly(token_program, false),
+        ]);
+    }
+    for mint in [request_x, request_y] {
+        make_accounts.extend([AccountMeta::new_readonly(mint, false), AccountMeta::new_readonly(mint_entry_address(&mint), false)]);
+    }
+
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: make_accounts,
+        data: anchor_escrow::instruction::MakeBasket { seed: 1701u64, deposits: deposits.clone(), requests: requests.clone() }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Basket make transaction successful");
+
+    assert_eq!(get_token_amount(&program, &vault_x), 100, "Vault X should hold 100 tokens");
+    assert_eq!(get_token_amount(&program, &vault_y), 200, "Vault Y should hold 200 tokens");
+
+    let basket_account = program.get_account(&basket).unwrap();
+    let basket_data = anchor_escrow::state::Basket::try_deserialize(&mut basket_account.data.as_ref()).unwrap();
+    assert_eq!(basket_data.maker, maker);
+    assert_eq!(basket_data.deposits, deposits);
+    assert_eq!(basket_data.requests, requests);
+
+    // Deposit legs: mint, vault, taker ATA, token program. Request legs: mint, taker ATA, maker ATA, treasury ATA, token program
+    let taker_ata_x = associated_token::get_associated_token_address(&taker.pubkey(), &deposit_x);
+    let taker_ata_y = associated_token::get_associated_token_address_with_program_id(&taker.pubkey(), &deposit_y, &TOKEN_2022_PROGRAM_ID);
+    let maker_request_ata_x = associated_token::get_associated_token_address(&maker, &request_x);
+    let maker_request_ata_y = associated_token::get_associated_token_address(&maker, &request_y);
+
+    let mut take_accounts = anchor_escrow::accounts::TakeBasket {
+        taker: taker.pubkey(), maker, basket, config: config_address(), treasury: payer.pubkey(),
+        associated_token_program: spl_associated_token_account::ID, system_program: SYSTEM_PROGRAM_ID,
+    }.to_account_metas(None);
+    for (mint, vault, taker_ata, token_program) in [(deposit_x, vault_x, taker_ata_x, TOKEN_PROGRAM_ID), (deposit_y, vault_y, taker_ata_y, TOKEN_2022_PROGRAM_ID)] {
+        take_accounts.extend([
+            AccountMeta::new(mint, false),
+            AccountMeta::new(vault, false),
+            AccountMeta::new(taker_ata, false),
+            AccountMeta::new_readonly(token_program, false),
+        ]);
+    }
+    for (mint, taker_ata, maker_ata) in [(request_x, taker_request_ata_x, maker_request_ata_x), (request_y, taker_request_ata_y, maker_request_ata_y)] {
+        take_accounts.extend([
+            AccountMeta::new_readonly(mint, false),
+            AccountMeta::new(taker_ata, false),
+            AccountMeta::new(maker_ata, false),
+            AccountMeta::new(associated_token::get_associated_token_address(&payer.pubkey(), &mint), false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+        ]);
+    }
+
+    // Dropping the last leg breaks atomic settlement (should fail with InvalidBasketAccounts)
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: take_accounts[..take_accounts.len() - 5].to_vec(),
+        data: anchor_escrow::instruction::TakeBasket {}.data(),
+    };
+
+    let result = program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash()));
+
+    assert!(result.is_err(), "Take should fail when a leg is missing");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1786") || error_msg.contains("6022"),
+        "Error should be InvalidBasketAccounts (6022/0x1786), got: {}", error_msg);
+
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: take_accounts,
+        data: anchor_escrow::instruction::TakeBasket {}.data(),
+    };
+
+    let tx = program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Basket take transaction successful");
+    msg!("CUs Consumed: {}", tx.compute_units_consumed);
+
+    assert_eq!(get_token_amount(&program, &taker_ata_x), 100, "Taker should have received all of deposit X");
+    assert_eq!(get_token_amount(&program, &taker_ata_y), 200, "Taker should have received all of deposit Y");
+    assert_eq!(get_token_amount(&program, &maker_request_ata_x), 30, "Maker should have received request X");
+    assert_eq!(get_token_amount(&program, &maker_request_ata_y), 40, "Maker should have received request Y");
+
+    for closed in [vault_x, vault_y, basket] {
+        assert_eq!(program.get_account(&closed).map_or(0, |acc| acc.lamports), 0, "Vaults and basket should be closed");
+    }
+
+    let event = find_event::<BasketTaken>(&tx.logs).expect("BasketTaken event should be emitted");
+    assert_eq!(event.taker, taker.pubkey());
+    assert_eq!(event.deposits, deposits);
+    assert_eq!(event.requests, requests);
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_refund_basket() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+
+    let deposit_x = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let request_x = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_x = CreateAssociatedTokenAccount::new(&mut program, &payer, &deposit_x).owner(&maker).send().unwrap();
+    MintTo::new(&mut program, &payer, &deposit_x, &maker_ata_x, 1000000000).send().unwrap();
+
+    let basket = Pubkey::find_program_address(&[b"basket", maker.as_ref(), &1702u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault_x = associated_token::get_associated_token_address(&basket, &deposit_x);
+
+    let make_accounts = |requested: Pubkey| {
+        let mut accounts = anchor_escrow::accounts::MakeBasket {
+            maker, basket, config: config_address(), associated_token_program: spl_associated_token_account::ID, system_program: SYSTEM_PROGRAM_ID,
+        }.to_account_metas(None);
+        accounts.extend([
+            AccountMeta::new_readonly(deposit_x, false),
+            AccountMeta::new_readonly(mint_entry_address(&deposit_x), false),
+            AccountMeta::new(maker_ata_x, false),
+            AccountMeta::new(vault_x, false),
+            AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+            AccountMeta::new_readonly(requested, false),
+            AccountMeta::new_readonly(mint_entry_address(&requested), false),
+        ]);
+        accounts
+    };
+
+    // Asking for a mint that is also deposited (should fail with SameMint)
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: make_accounts(deposit_x),
+        data: anchor_escrow::instruction::MakeBasket {
+            seed: 1702u64, deposits: vec![BasketLeg { mint: deposit_x, amount: 100 }], requests: vec![BasketLeg { mint: deposit_x, amount: 10 }],
+        }.data(),
+    };
+
+    let result = program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix], Some(&payer.pubkey())), program.latest_blockhash()));
+
+    assert!(result.is_err(), "Make should fail when a mint appears twice");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x177b") || error_msg.contains("6011"),
+        "Error should be SameMint (6011/0x177b), got: {}", error_msg);
+
+    // Basket legs have no room for hook accounts (should fail with TransferHookNotSupported)
+    let hooked_mint = create_transfer_hook_mint(&mut program, &payer, &maker, 6);
+
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: make_accounts(hooked_mint),
+        data: anchor_escrow::instruction::MakeBasket {
+            seed: 1702u64, deposits: vec![BasketLeg { mint: deposit_x, amount: 100 }], requests: vec![BasketLeg { mint: hooked_mint, amount: 10 }],
+        }.data(),
+    };
+
+    let result = program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix], Some(&payer.pubkey())), program.latest_blockhash()));
+
+    assert!(result.is_err(), "Make should fail for a transfer hook mint");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x178f") || error_msg.contains("6031"),
+        "Error should be TransferHookNotSupported (6031/0x178f), got: {}", error_msg);
+
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: make_accounts(request_x),
+        data: anchor_escrow::instruction::MakeBasket {
+            seed: 1702u64, deposits: vec![BasketLeg { mint: deposit_x, amount: 100 }], requests: vec![BasketLeg { mint: request_x, amount: 10 }],
+        }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    assert_eq!(get_token_amount(&program, &maker_ata_x), 1000000000 - 100, "Maker should have deposited 100 tokens");
+
+    // Deposit legs: mint, vault, maker ATA, token program
+    let mut refund_accounts = anchor_escrow::accounts::RefundBasket {
+        maker, basket, associated_token_program: spl_associated_token_account::ID, system_program: SYSTEM_PROGRAM_ID,
+    }.to_account_metas(None);
+    refund_accounts.extend([
+        AccountMeta::new(deposit_x, false),
+        AccountMeta::new(vault_x, false),
+        AccountMeta::new(maker_ata_x, false),
+        AccountMeta::new_readonly(TOKEN_PROGRAM_ID, false),
+    ]);
+
+    let refund_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: refund_accounts,
+        data: anchor_escrow::instruction::RefundBasket {}.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[refund_ix], Some(&payer.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Basket refund transaction successful");
+
+    assert_eq!(get_token_amount(&program, &maker_ata_x), 1000000000, "Maker should have all tokens back");
+    assert_eq!(program.get_account(&vault_x).map_or(0, |acc| acc.lamports), 0, "Vault should be closed");
+    assert_eq!(program.get_account(&basket).map_or(0, |acc| acc.lamports), 0, "Basket should be closed");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_make_nft_deposit() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let nft_args = |seed: u64, deposit: u64| anchor_escrow::instruction::Make {
+        deposit, seed, receive: 500, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None,
+        nft: Some(NftTerms { deposit_is_nft: true, any_from_collection: false }), auction: None,
+    };
+
+    // A fungible mint cannot be offered as an NFT (should fail with NotAnNft)
+    let fungible_mint = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let maker_ata_fungible = CreateAssociatedTokenAccount::new(&mut program, &payer, &fungible_mint).owner(&maker).send().unwrap();
+    MintTo::new(&mut program, &payer, &fungible_mint, &maker_ata_fungible, 1000000000).send().unwrap();
+
+    let result = send_make(&mut program, &payer, fungible_mint, mint_b, nft_args(1801, 1));
+    assert!(result.is_err(), "Make should fail for a fungible mint in NFT mode");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1787") || error_msg.contains("6023"),
+        "Error should be NotAnNft (6023/0x1787), got: {}", error_msg);
+
+    // A 0-decimal, supply-1 mint whose authority is still live could be minted again (should fail with NotAnNft)
+    let mintable_mint = CreateMint::new(&mut program, &payer).decimals(0).authority(&maker).send().unwrap();
+    let maker_ata_mintable = CreateAssociatedTokenAccount::new(&mut program, &payer, &mintable_mint).owner(&maker).send().unwrap();
+    MintTo::new(&mut program, &payer, &mintable_mint, &maker_ata_mintable, 1).send().unwrap();
+
+    let result = send_make(&mut program, &payer, mintable_mint, mint_b, nft_args(1802, 1));
+    assert!(result.is_err(), "Make should fail for an NFT that can still be minted");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1787") || error_msg.contains("6023"),
+        "Error should be NotAnNft (6023/0x1787), got: {}", error_msg);
+
+    // A real NFT is escrowed as a single unit
+    let (nft, _) = create_nft(&mut program, &payer, &maker);
+
+    let tx = send_make(&mut program, &payer, nft, mint_b, nft_args(1803, 1)).unwrap();
+
+    let made = find_event::<EscrowMade>(&tx.logs).expect("EscrowMade event should be emitted");
+    assert_eq!(made.deposit, 1, "Event should record the single NFT deposited");
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &1803u64.to_le_bytes()], &PROGRAM_ID).0;
+    let escrow_data = anchor_escrow::state::Escrow::try_deserialize(&mut program.get_account(&escrow).unwrap().data.as_ref()).unwrap();
+    assert!(escrow_data.nft.deposit_is_nft, "Escrow should be in NFT mode");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_any_from_collection() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    // Maker offers a fungible mint for any NFT from the collection
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+
+    let collection = create_collection(&mut program, &payer);
+    let (member, taker_ata_member) = create_collection_member(&mut program, &payer, &collection, &taker.pubkey());
+
+    // An NFT from a different collection, also held by the taker
+    let other_collection = create_collection(&mut program, &payer);
+    let (outsider, taker_ata_outsider) = create_collection_member(&mut program, &payer, &other_collection, &taker.pubkey());
+
+    let associated_token_program = spl_associated_token_account::ID;
+    let system_program = SYSTEM_PROGRAM_ID;
+
+    let make_ix = |seed: u64, mint_b: Pubkey, receive: u64| {
+        let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &seed.to_le_bytes()], &PROGRAM_ID).0;
+        Instruction {
+            program_id: PROGRAM_ID,
+            accounts: anchor_escrow::accounts::Make {
+                maker, mint_a, mint_b, maker_ata_a: Some(maker_ata_a), escrow,
+                vault: associated_token::get_associated_token_address(&escrow, &mint_a), associated_token_program,
+                token_program_a: TOKEN_PROGRAM_ID, token_program_b: TOKEN_2022_PROGRAM_ID, system_program,
+                config: config_address(),
+                mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+            }.to_account_metas(None),
+            data: anchor_escrow::instruction::Make {
+                deposit: 1000, seed, receive, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None,
+                nft: Some(NftTerms { deposit_is_nft: false, any_from_collection: true }), auction: None,
+            }.data(),
+        }
+    };
+
+    // A member NFT is not a collection (should fail with InvalidCollection)
+    let result = program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix(1804, member, 1)], Some(&maker)), program.latest_blockhash()));
+    assert!(result.is_err(), "Make should fail when mint B is not a collection");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1788") || error_msg.contains("6024"),
+        "Error should be InvalidCollection (6024/0x1788), got: {}", error_msg);
+
+    // Collection offers are filled by exactly one NFT (should fail with InvalidCollection)
+    let result = program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix(1805, collection, 2)], Some(&maker)), program.latest_blockhash()));
+    assert!(result.is_err(), "Make should fail when more than one NFT is requested");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1788") || error_msg.contains("6024"),
+        "Error should be InvalidCollection (6024/0x1788), got: {}", error_msg);
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix(1806, collection, 1)], Some(&maker)), program.latest_blockhash())).unwrap();
+
+    msg!("Collection offer made");
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &1806u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+    let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+
+    let take_ix = |mint_b: Pubkey, taker_ata_b: Pubkey| Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Take {
+            taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b),
+            maker_ata_b: Some(associated_token::get_associated_token_address_with_program_id(&maker, &mint_b, &TOKEN_2022_PROGRAM_ID)),
+            escrow, vault, associated_token_program,
+            token_program_a: TOKEN_PROGRAM_ID, token_program_b: TOKEN_2022_PROGRAM_ID, system_program,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    // An NFT from another collection (should fail with NotInCollection)
+    let result = program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix(outsider, taker_ata_outsider)], Some(&taker.pubkey())), program.latest_blockhash()));
+    assert!(result.is_err(), "Take should fail with an NFT from another collection");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1789") || error_msg.contains("6025"),
+        "Error should be NotInCollection (6025/0x1789), got: {}", error_msg);
+
+    let tx = program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix(member, taker_ata_member)], Some(&taker.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Collection offer filled by a member NFT");
+
+    let taken = find_event::<EscrowTaken>(&tx.logs).expect("EscrowTaken event should be emitted");
+    assert_eq!(taken.mint_b, member, "Event should record the NFT actually paid");
+
+    let maker_ata_member = associated_token::get_associated_token_address_with_program_id(&maker, &member, &TOKEN_2022_PROGRAM_ID);
+    assert_eq!(get_token_amount(&program, &maker_ata_member), 1, "Maker should have received the member NFT");
+    assert_eq!(get_token_amount(&program, &taker_ata_member), 0, "Taker should have paid the member NFT");
+    assert_eq!(get_token_amount(&program, &taker_ata_a), 1000, "Taker should have received the whole deposit");
+    assert_eq!(program.get_account(&escrow).map_or(0, |acc| acc.lamports), 0, "Escrow should be closed");
+
+    msg!("All assertions passed!");
+}
+
+fn bid_ix(maker: Pubkey, bidder: Pubkey, mint_b: Pubkey, escrow: Pubkey, amount_a: u64, amount_b: u64) -> (Instruction, Pubkey, Pubkey) {
+    // Bid from the bidder's legacy SPL Token ATA, returns the bid and its vault alongside the instruction
+    let bid = Pubkey::find_program_address(&[b"bid", escrow.as_ref(), bidder.as_ref()], &PROGRAM_ID).0;
+    let bid_vault = associated_token::get_associated_token_address(&bid, &mint_b);
+
+    let ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::PlaceBid {
+            bidder, maker, mint_b, bidder_ata_b: associated_token::get_associated_token_address(&bidder, &mint_b),
+            escrow, bid, bid_vault, config: config_address(),
+            associated_token_program: spl_associated_token_account::ID, token_program: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Bid { amount_a, amount_b }.data(),
+    };
+
+    (ix, bid, bid_vault)
+}
+
+#[test]
+fn test_accept_bid() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let bidder = Keypair::new();
+
+    program.airdrop(&bidder.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    let bidder_ata_b = CreateAssociatedTokenAccount::new(&mut program, &bidder, &mint_b).owner(&bidder.pubkey()).send().unwrap();
+
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_b, &bidder_ata_b, 1000000000).send().unwrap();
+
+    // Make: deposit 100 of Mint A, wants 50 of Mint B
+    send_make(&mut program, &payer, mint_a, mint_b, anchor_escrow::instruction::Make {
+        deposit: 100, seed: 1901u64, receive: 50, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None,
+    }).unwrap();
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &1901u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+
+    // Asking for more than the vault holds (should fail with InvalidFillAmount)
+    let (too_large_ix, _, _) = bid_ix(maker, bidder.pubkey(), mint_b, escrow, 101, 20);
+
+    let result = program.send_transaction(Transaction::new(&[&bidder], Message::new(&[too_large_ix], Some(&bidder.pubkey())), program.latest_blockhash()));
+    assert!(result.is_err(), "Bid should fail when asking for more than the deposit");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x1771") || error_msg.contains("6001"),
+        "Error should be InvalidFillAmount (6001/0x1771), got: {}", error_msg);
+
+    // Counter-offer: 20 Mint B for 50 of Mint A, below the maker's price of 25
+    let (place_ix, bid, bid_vault) = bid_ix(maker, bidder.pubkey(), mint_b, escrow, 50, 20);
+
+    let tx = program.send_transaction(Transaction::new(&[&bidder], Message::new(&[place_ix], Some(&bidder.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Bid placed");
+
+    let placed = find_event::<BidPlaced>(&tx.logs).expect("BidPlaced event should be emitted");
+    assert_eq!(placed.amount_a, 50);
+    assert_eq!(placed.amount_b, 20);
+    assert_eq!(get_token_amount(&program, &bid_vault), 20, "Bid vault should hold the bidder's 20 Mint B");
+    assert_eq!(get_token_amount(&program, &bidder_ata_b), 1000000000 - 20, "Bidder should have deposited 20 Mint B");
+
+    let bidder_ata_a = associated_token::get_associated_token_address(&bidder.pubkey(), &mint_a);
+    let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+
+    let accept_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::AcceptBid {
+            maker, bidder: bidder.pubkey(), mint_a, mint_b, bidder_ata_a: Some(bidder_ata_a), maker_ata_a: None, maker_ata_b,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None,
+            escrow, vault, bid, bid_vault,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program_a: TOKEN_PROGRAM_ID, token_program_b: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::AcceptBid {}.data(),
+    };
+
+    let tx = program.send_transaction(Transaction::new(&[&payer], Message::new(&[accept_ix], Some(&maker)), program.latest_blockhash())).unwrap();
+
+    msg!("Bid accepted");
+
+    let accepted = find_event::<BidAccepted>(&tx.logs).expect("BidAccepted event should be emitted");
+    assert_eq!(accepted.amount_a, 50);
+    assert_eq!(accepted.amount_b, 20);
+    assert_eq!(accepted.remaining_receive, 25);
+
+    assert_eq!(get_token_amount(&program, &bidder_ata_a), 50, "Bidder should have received 50 Mint A");
+    assert_eq!(get_token_amount(&program, &maker_ata_b), 20, "Maker should have received the bid's 20 Mint B");
+    assert_eq!(get_token_amount(&program, &vault), 50, "Vault should still hold 50 Mint A");
+
+    // The rest of the escrow keeps the maker's original price
+    let escrow_data = anchor_escrow::state::Escrow::try_deserialize(&mut program.get_account(&escrow).unwrap().data.as_ref()).unwrap();
+    assert_eq!(escrow_data.deposit, 50);
+    assert_eq!(escrow_data.receive, 25);
+
+    assert_eq!(program.get_account(&bid).map_or(0, |acc| acc.lamports), 0, "Bid should be closed");
+    assert_eq!(program.get_account(&bid_vault).map_or(0, |acc| acc.lamports), 0, "Bid vault should be closed");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_accept_bid_with_native_sol_deposit() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let bidder = Keypair::new();
+
+    program.airdrop(&bidder.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    // Maker deposits plain lamports, the bidder offers Mint B for half of them
+    let mint_a = spl_token::native_mint::ID;
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let bidder_ata_b = CreateAssociatedTokenAccount::new(&mut program, &bidder, &mint_b).owner(&bidder.pubkey()).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_b, &bidder_ata_b, 1000000000).send().unwrap();
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &1902u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+
+    let make_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Make {
+            maker, mint_a, mint_b, maker_ata_a: None, escrow, vault,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program_a: TOKEN_PROGRAM_ID, token_program_b: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+            config: config_address(),
+            mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Make { deposit: LAMPORTS_PER_SOL, seed: 1902u64, receive: 20, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None }.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[make_ix], Some(&maker)), program.latest_blockhash())).unwrap();
+
+    let (place_ix, bid, bid_vault) = bid_ix(maker, bidder.pubkey(), mint_b, escrow, LAMPORTS_PER_SOL / 2, 5);
+
+    program.send_transaction(Transaction::new(&[&bidder], Message::new(&[place_ix], Some(&bidder.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Bid placed on native SOL deposit");
+
+    // Only the maker signs, so the bidder's share is unwrapped through the maker's wSOL account
+    let maker_ata_a = associated_token::get_associated_token_address(&maker, &mint_a);
+    let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+
+    let accept_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::AcceptBid {
+            maker, bidder: bidder.pubkey(), mint_a, mint_b, bidder_ata_a: None, maker_ata_a: Some(maker_ata_a), maker_ata_b,
+            config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None,
+            escrow, vault, bid, bid_vault,
+            associated_token_program: spl_associated_token_account::ID,
+            token_program_a: TOKEN_PROGRAM_ID, token_program_b: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::AcceptBid {}.data(),
+    };
+
+    // The bid and its vault close back to the bidder alongside the unwrapped deposit
+    let bid_rent = program.get_balance(&bid).unwrap() + program.get_balance(&bid_vault).unwrap();
+    let bidder_lamports_before = program.get_balance(&bidder.pubkey()).unwrap();
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[accept_ix], Some(&maker)), program.latest_blockhash())).unwrap();
+
+    msg!("Bid accepted, bidder paid out in lamports");
+
+    let bidder_lamports_after = program.get_balance(&bidder.pubkey()).unwrap();
+    assert_eq!(bidder_lamports_after, bidder_lamports_before + LAMPORTS_PER_SOL / 2 + bid_rent, "Bidder should have received half the deposit as lamports");
+    assert!(program.get_account(&maker_ata_a).is_none_or(|acc| acc.lamports == 0), "Maker wSOL account should be closed");
+    assert_eq!(get_token_amount(&program, &maker_ata_b), 5, "Maker should have received the bid's 5 Mint B");
+    assert_eq!(get_token_amount(&program, &vault), LAMPORTS_PER_SOL / 2, "Vault should still hold the other half");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_cancel_bid_after_refund() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let bidder = Keypair::new();
+
+    program.airdrop(&bidder.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    let bidder_ata_b = CreateAssociatedTokenAccount::new(&mut program, &bidder, &mint_b).owner(&bidder.pubkey()).send().unwrap();
+
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_b, &bidder_ata_b, 1000000000).send().unwrap();
+
+    send_make(&mut program, &payer, mint_a, mint_b, anchor_escrow::instruction::Make {
+        deposit: 100, seed: 1902u64, receive: 50, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None,
+    }).unwrap();
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &1902u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+
+    let (place_ix, bid, bid_vault) = bid_ix(maker, bidder.pubkey(), mint_b, escrow, 100, 30);
+    program.send_transaction(Transaction::new(&[&bidder], Message::new(&[place_ix], Some(&bidder.pubkey())), program.latest_blockhash())).unwrap();
+
+    // The maker walks away instead of accepting
+    let refund_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::Refund {
+            maker, mint_a, maker_ata_a: Some(maker_ata_a), escrow, vault, token_program: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::Refund {}.data(),
+    };
+
+    program.send_transaction(Transaction::new(&[&payer], Message::new(&[refund_ix], Some(&maker)), program.latest_blockhash())).unwrap();
+
+    // The bid no longer needs the escrow to be withdrawn
+    let cancel_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::CancelBid {
+            bidder: bidder.pubkey(), mint_b, bidder_ata_b, bid, bid_vault, token_program: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::CancelBid {}.data(),
+    };
+
+    let tx = program.send_transaction(Transaction::new(&[&bidder], Message::new(&[cancel_ix], Some(&bidder.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Bid cancelled");
+
+    let cancelled = find_event::<BidCancelled>(&tx.logs).expect("BidCancelled event should be emitted");
+    assert_eq!(cancelled.amount_b, 30);
+
+    assert_eq!(get_token_amount(&program, &bidder_ata_b), 1000000000, "Bidder should have all Mint B back");
+    assert_eq!(program.get_account(&bid).map_or(0, |acc| acc.lamports), 0, "Bid should be closed");
+    assert_eq!(program.get_account(&bid_vault).map_or(0, |acc| acc.lamports), 0, "Bid vault should be closed");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_take_dutch_auction() {
+    // Setup
+    let (mut program, payer) = setup();
+    let maker = payer.pubkey();
+    let taker = Keypair::new();
+
+    program.airdrop(&taker.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+    let mint_a = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+    let mint_b = CreateMint::new(&mut program, &payer).decimals(6).authority(&maker).send().unwrap();
+
+    let maker_ata_a = CreateAssociatedTokenAccount::new(&mut program, &payer, &mint_a).owner(&maker).send().unwrap();
+    let taker_ata_b = CreateAssociatedTokenAccount::new(&mut program, &taker, &mint_b).owner(&taker.pubkey()).send().unwrap();
+
+    MintTo::new(&mut program, &payer, &mint_a, &maker_ata_a, 1000000000).send().unwrap();
+    MintTo::new(&mut program, &payer, &mint_b, &taker_ata_b, 1000000000).send().unwrap();
+
+    // Price falls from 1000 to 200 Mint B over 100 slots
+    let auction_args = |seed: u64, auction: DutchAuction| anchor_escrow::instruction::Make {
+        deposit: 100, seed, receive: 1000, lock_kind: LockKind::Slot, lock_period: 0, commit_period: 0, expires_at: None, taker: None,
+        nft: None, auction: Some(auction),
+    };
+
+    // An end price above the start price (should fail with InvalidAuction)
+    let result = send_make(&mut program, &payer, mint_a, mint_b, auction_args(2001, DutchAuction { end_receive: 1001, duration: 100 }));
+    assert!(result.is_err(), "Make should fail with an end price above the start price");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x178a") || error_msg.contains("6026"),
+        "Error should be InvalidAuction (6026/0x178a), got: {}", error_msg);
+
+    // An auction that never decays (should fail with InvalidAuction)
+    let result = send_make(&mut program, &payer, mint_a, mint_b, auction_args(2002, DutchAuction { end_receive: 200, duration: 0 }));
+    assert!(result.is_err(), "Make should fail with a zero duration");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x178a") || error_msg.contains("6026"),
+        "Error should be InvalidAuction (6026/0x178a), got: {}", error_msg);
+
+    let start_slot = program.get_sysvar::<anchor_lang::solana_program::clock::Clock>().slot;
+    send_make(&mut program, &payer, mint_a, mint_b, auction_args(2003, DutchAuction { end_receive: 200, duration: 100 })).unwrap();
+
+    msg!("Dutch auction made at slot {}", start_slot);
+
+    let escrow = Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &2003u64.to_le_bytes()], &PROGRAM_ID).0;
+    let vault = associated_token::get_associated_token_address(&escrow, &mint_a);
+    let taker_ata_a = associated_token::get_associated_token_address(&taker.pubkey(), &mint_a);
+    let maker_ata_b = associated_token::get_associated_token_address(&maker, &mint_b);
+
+    let take_accounts = anchor_escrow::accounts::Take {
+        taker: taker.pubkey(), maker, mint_a, mint_b, taker_ata_a, taker_ata_b: Some(taker_ata_b), maker_ata_b: Some(maker_ata_b),
+        escrow, vault, associated_token_program: spl_associated_token_account::ID,
+        token_program_a: TOKEN_PROGRAM_ID, token_program_b: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+        config: config_address(), treasury: payer.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+    };
+
+    // Halfway through the price is 600
+    program.warp_to_slot(start_slot + 50);
+
+    // Auctions cannot be split (should fail with AuctionNotDivisible)
+    let take_partial_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: take_accounts.to_account_metas(None),
+        data: anchor_escrow::instruction::TakePartial { amount_b: 300, min_amount_a: 0, referrer_fee_bps: 0 }.data(),
+    };
+
+    let result = program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_partial_ix], Some(&taker.pubkey())), program.latest_blockhash()));
+    assert!(result.is_err(), "Partial take of an auction should fail");
+    let error_msg = format!("{:?}", result.unwrap_err());
+    assert!(error_msg.contains("0x178b") || error_msg.contains("6027"),
+        "Error should be AuctionNotDivisible (6027/0x178b), got: {}", error_msg);
+
+    let take_ix = Instruction {
+        program_id: PROGRAM_ID,
+        accounts: take_accounts.to_account_metas(None),
+        data: anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 }.data(),
+    };
+
+    let tx = program.send_transaction(Transaction::new(&[&taker], Message::new(&[take_ix], Some(&taker.pubkey())), program.latest_blockhash())).unwrap();
+
+    msg!("Dutch auction taken");
+
+    let taken = find_event::<EscrowTaken>(&tx.logs).expect("EscrowTaken event should be emitted");
+    assert_eq!(taken.amount_b, 600, "Taker should pay the decayed price");
+
+    assert_eq!(get_token_amount(&program, &taker_ata_a), 100, "Taker should have received the whole deposit");
+    assert_eq!(get_token_amount(&program, &maker_ata_b), 600, "Maker should have received the decayed price");
+    assert_eq!(program.get_account(&escrow).map_or(0, |acc| acc.lamports), 0, "Escrow should be closed");
+
+    msg!("All assertions passed!");
+}
+
+#[test]
+fn test_load_fixtures() {
+    // Fixtures load without the program or the network
+    let mut program = LiteSVM::new();
+
+    load_all_fixtures(&mut program);
+
+    let native_mint = program.get_account(&spl_token::native_mint::ID).expect("wSOL mint fixture should be loaded");
+    assert_eq!(native_mint.owner, TOKEN_PROGRAM_ID, "wSOL mint should be owned by the token program");
+
+    let mint_data = spl_token::state::Mint::unpack(&native_mint.data).unwrap();
+    assert_eq!(mint_data.decimals, spl_token::native_mint::DECIMALS);
+    assert!(mint_data.is_initialized, "wSOL mint should be initialized");
+
+    msg!("All assertions passed!");
+}
@@ -0,0 +1,180 @@
+// Property-based scenarios: random make, take, refund and warp sequences across several makers,
+// takers and seeds, with the token conservation and lock invariants checked after every step.
+
+use {
+    litesvm::types::TransactionResult,
+    proptest::prelude::*,
+    solana_keypair::Keypair,
+    solana_pubkey::Pubkey,
+    solana_signer::Signer,
+};
+
+use anchor_escrow::state::{Escrow, LockKind};
+use anchor_escrow_harness::{EscrowTestEnv, STARTING_BALANCE};
+
+const MAKERS: usize = 2;
+const TAKERS: usize = 2;
+const SEEDS: u64 = 3; // Per maker, so seeds are reused once their escrow closes
+
+#[derive(Clone, Debug)]
+enum Step {
+    Make { maker: usize, seed: u64, deposit: u64, receive: u64, lock_period: i64, commit_period: i64 },
+    Take { taker: usize, maker: usize, seed: u64 },
+    TakePartial { taker: usize, maker: usize, seed: u64, amount_b: u64 },
+    Refund { maker: usize, seed: u64 },
+    Warp { slots: u64 },
+}
+
+fn step() -> impl Strategy<Value = Step> {
+    prop_oneof![
+        3 => (0..MAKERS, 0..SEEDS, 1..=1_000_000u64, 1..=1_000_000u64, 0..=20i64, 0..=20i64).prop_map(
+            |(maker, seed, deposit, receive, lock_period, commit_period)| Step::Make { maker, seed, deposit, receive, lock_period, commit_period }
+        ),
+        2 => (0..TAKERS, 0..MAKERS, 0..SEEDS).prop_map(|(taker, maker, seed)| Step::Take { taker, maker, seed }),
+        2 => (0..TAKERS, 0..MAKERS, 0..SEEDS, 1..=1_000_000u64).prop_map(
+            |(taker, maker, seed, amount_b)| Step::TakePartial { taker, maker, seed, amount_b }
+        ),
+        1 => (0..MAKERS, 0..SEEDS).prop_map(|(maker, seed)| Step::Refund { maker, seed }),
+        2 => (1..=10u64).prop_map(|slots| Step::Warp { slots }),
+    ]
+}
+
+// Makers start with mint A and takers with mint B, no protocol fee is configured. The env's own
+// maker is the mint authority and treasury and never trades, but holds mint A too
+struct World {
+    env: EscrowTestEnv,
+    makers: Vec<Keypair>,
+    takers: Vec<Keypair>,
+}
+
+impl World {
+    fn new(decimals_a: u8, decimals_b: u8) -> Self {
+        let mut env = EscrowTestEnv::new().with_mint_decimals(decimals_a, decimals_b);
+
+        let makers = (0..MAKERS).map(|_| env.new_maker()).collect();
+        let takers = (0..TAKERS).map(|_| env.new_taker()).collect();
+
+        Self { env, makers, takers }
+    }
+
+    fn apply(&mut self, step: &Step) -> Result<(), TestCaseError> {
+        // Repeated steps would otherwise be rejected as already processed
+        self.env.program.expire_blockhash();
+
+        match *step {
+            Step::Make { maker, seed, deposit, receive, lock_period, commit_period } => {
+                let before = self.escrow_state(maker, seed);
+                let result = self.make(maker, seed, deposit, receive, lock_period, commit_period);
+
+                prop_assert_eq!(result.is_ok(), before.is_none(), "Make should succeed only for an unused seed");
+
+                if result.is_ok() {
+                    let escrow = self.escrow_state(maker, seed).unwrap();
+                    prop_assert_eq!(escrow.start_time, self.env.slot() as i64);
+                    prop_assert_eq!((escrow.deposit, escrow.receive), (deposit, receive));
+                }
+            }
+            Step::Take { taker, maker, seed } => {
+                let before = self.escrow_state(maker, seed);
+                let result = self.env.take_from(
+                    &self.takers[taker], &self.makers[maker].pubkey(), seed,
+                    anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 },
+                );
+
+                // With no fee, expiry or designated taker only the lock can refuse an open escrow
+                let takeable = before.as_ref().is_some_and(|escrow| self.unlocked(escrow));
+                prop_assert_eq!(result.is_ok(), takeable, "Take should succeed exactly once the lock has elapsed");
+                prop_assert!(self.escrow_state(maker, seed).is_none() || result.is_err(), "A full take should close the escrow");
+            }
+            Step::TakePartial { taker, maker, seed, amount_b } => {
+                let before = self.escrow_state(maker, seed);
+                let result = self.env.take_partial_from(
+                    &self.takers[taker], &self.makers[maker].pubkey(), seed,
+                    anchor_escrow::instruction::TakePartial { amount_b, min_amount_a: 0, referrer_fee_bps: 0 },
+                );
+
+                // A fill must stay within the remaining terms and release at least one unit of mint A
+                let fillable = before.as_ref().is_some_and(|escrow| {
+                    self.unlocked(escrow) && amount_b <= escrow.receive && escrow.share_of_deposit(amount_b) > 0
+                });
+                prop_assert_eq!(result.is_ok(), fillable, "A fill should succeed exactly when it is valid and the lock has elapsed");
+            }
+            Step::Refund { maker, seed } => {
+                let before = self.escrow_state(maker, seed);
+                let result = self.env.refund_as(&self.makers[maker], seed);
+
+                let refundable = before.as_ref().is_some_and(|escrow| self.env.slot() as i64 >= escrow.commit_ends_at().unwrap());
+                prop_assert_eq!(result.is_ok(), refundable, "Refund should succeed exactly once the commit period has elapsed");
+            }
+            Step::Warp { slots } => {
+                let slot = self.env.slot();
+                self.env.program.warp_to_slot(slot + slots);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn check_invariants(&self) -> Result<(), TestCaseError> {
+        let escrows: Vec<(Pubkey, u64)> = self.makers.iter().flat_map(|maker| (0..SEEDS).map(move |seed| (maker.pubkey(), seed))).collect();
+
+        for &(maker, seed) in &escrows {
+            let (escrow, vault) = (self.env.escrow_of(&maker, seed), self.env.vault_of(&maker, seed));
+
+            match self.env.escrow_state_of(&maker, seed) {
+                Some(state) => {
+                    prop_assert!(state.deposit > 0 && state.receive > 0, "Open escrow should have something left to trade");
+                    prop_assert_eq!(self.env.balance(&vault), state.deposit, "Vault should hold exactly the escrow's deposit");
+                }
+                None => {
+                    prop_assert_eq!(self.env.lamports(&escrow), 0, "Closed escrow should have zero lamports");
+                    prop_assert_eq!(self.env.lamports(&vault), 0, "Closed vault should have zero lamports");
+                }
+            }
+        }
+
+        let (mint_a, mint_b) = (self.env.mint_a, self.env.mint_b);
+        let wallets: Vec<Pubkey> = [&self.env.maker].into_iter().chain(&self.makers).chain(&self.takers).map(|wallet| wallet.pubkey()).collect();
+
+        let vaults_a = escrows.iter().map(|(maker, seed)| self.env.balance(&self.env.vault_of(maker, *seed))).sum::<u64>();
+        let wallets_a = wallets.iter().map(|wallet| self.env.balance_a(wallet)).sum::<u64>();
+        let wallets_b = wallets.iter().map(|wallet| self.env.balance_b(wallet)).sum::<u64>();
+
+        prop_assert_eq!(self.env.supply(&mint_a), STARTING_BALANCE * (MAKERS as u64 + 1), "Mint A supply changed");
+        prop_assert_eq!(self.env.supply(&mint_b), STARTING_BALANCE * TAKERS as u64, "Mint B supply changed");
+        prop_assert_eq!(vaults_a + wallets_a, self.env.supply(&mint_a), "Mint A leaked out of the known accounts");
+        prop_assert_eq!(wallets_b, self.env.supply(&mint_b), "Mint B leaked out of the known accounts");
+
+        Ok(())
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn make(&mut self, maker: usize, seed: u64, deposit: u64, receive: u64, lock_period: i64, commit_period: i64) -> TransactionResult {
+        self.env.make_as(&self.makers[maker], anchor_escrow::instruction::Make {
+            deposit, seed, receive, lock_kind: LockKind::Slot, lock_period, commit_period, expires_at: None, taker: None, nft: None, auction: None,
+        })
+    }
+
+    fn escrow_state(&self, maker: usize, seed: u64) -> Option<Escrow> {
+        self.env.escrow_state_of(&self.makers[maker].pubkey(), seed)
+    }
+
+    fn unlocked(&self, escrow: &Escrow) -> bool {
+        self.env.slot() as i64 >= escrow.lock_ends_at().unwrap()
+    }
+}
+
+proptest! {
+    // Every case deploys the program into a fresh LiteSVM, keep the count modest
+    #![proptest_config(ProptestConfig::with_cases(32))]
+
+    #[test]
+    fn test_escrow_invariants(decimals_a in 0..=9u8, decimals_b in 0..=9u8, steps in prop::collection::vec(step(), 1..40)) {
+        let mut world = World::new(decimals_a, decimals_b);
+
+        for step in &steps {
+            world.apply(step)?;
+            world.check_invariants()?;
+        }
+    }
+}
@@ -0,0 +1,301 @@
+// Scenario harness over `setup()`: one maker, a pair of legacy SPL Token mints and the
+// escrow made last, so tests only spell out what they actually exercise. The `_as`/`_from`
+// variants act for any other maker and escrow, for scenarios with several of each.
+
+use {
+    anchor_lang::{solana_program::program_pack::Pack, AccountDeserialize, InstructionData, ToAccountMetas},
+    anchor_spl::{
+        associated_token::{self, spl_associated_token_account},
+        token::spl_token,
+    },
+    litesvm::{types::TransactionResult, LiteSVM},
+    litesvm_token::{spl_token::ID as TOKEN_PROGRAM_ID, CreateAssociatedTokenAccount, CreateMint, MintTo},
+    solana_instruction::Instruction,
+    solana_keypair::Keypair,
+    solana_native_token::LAMPORTS_PER_SOL,
+    solana_pubkey::Pubkey,
+    solana_sdk_ids::system_program::ID as SYSTEM_PROGRAM_ID,
+    solana_signer::Signer,
+};
+
+use anchor_escrow::state::{Escrow, LockKind};
+
+use crate::{config_address, get_token_amount, mint_entry_address, send, setup};
+
+// Minted to the maker's mint A account and to every taker's mint B account
+pub const STARTING_BALANCE: u64 = 1_000_000_000;
+
+pub struct EscrowTestEnv {
+    pub program: LiteSVM,
+    pub maker: Keypair, // Also the config admin and treasury
+    pub mint_a: Pubkey,
+    pub mint_b: Pubkey,
+    pub seed: u64, // Escrow targeted by take and refund, set by the last make
+}
+
+impl EscrowTestEnv {
+    // Deploys the program, so deliberately not `Default`
+    #[allow(clippy::new_without_default)]
+    pub fn new() -> Self {
+        let (program, maker) = setup();
+
+        Self { program, maker, mint_a: Pubkey::default(), mint_b: Pubkey::default(), seed: 0 }
+    }
+
+    pub fn with_mints(self, decimals: u8) -> Self {
+        self.with_mint_decimals(decimals, decimals)
+    }
+
+    pub fn with_mint_decimals(mut self, decimals_a: u8, decimals_b: u8) -> Self {
+        let maker = self.maker.pubkey();
+
+        self.mint_a = CreateMint::new(&mut self.program, &self.maker).decimals(decimals_a).authority(&maker).send().unwrap();
+        self.mint_b = CreateMint::new(&mut self.program, &self.maker).decimals(decimals_b).authority(&maker).send().unwrap();
+
+        let maker_ata_a = CreateAssociatedTokenAccount::new(&mut self.program, &self.maker, &self.mint_a).owner(&maker).send().unwrap();
+        MintTo::new(&mut self.program, &self.maker, &self.mint_a, &maker_ata_a, STARTING_BALANCE).send().unwrap();
+
+        self
+    }
+
+    // A funded taker holding mint B
+    pub fn new_taker(&mut self) -> Keypair {
+        let mint_b = self.mint_b;
+        self.new_wallet(&mint_b)
+    }
+
+    // Another funded maker holding mint A, for use with the `_as` and `_from` variants
+    pub fn new_maker(&mut self) -> Keypair {
+        let mint_a = self.mint_a;
+        self.new_wallet(&mint_a)
+    }
+
+    fn new_wallet(&mut self, mint: &Pubkey) -> Keypair {
+        let wallet = Keypair::new();
+
+        self.program.airdrop(&wallet.pubkey(), 10 * LAMPORTS_PER_SOL).unwrap();
+
+        let ata = CreateAssociatedTokenAccount::new(&mut self.program, &wallet, mint).owner(&wallet.pubkey()).send().unwrap();
+        MintTo::new(&mut self.program, &self.maker, mint, &ata, STARTING_BALANCE).send().unwrap();
+
+        wallet
+    }
+
+    // Slot-locked escrow with no commit period, expiry or designated taker
+    #[allow(clippy::result_large_err)]
+    pub fn make(&mut self, seed: u64, deposit: u64, receive: u64, lock_period: i64) -> TransactionResult {
+        self.make_with(anchor_escrow::instruction::Make {
+            deposit, seed, receive, lock_kind: LockKind::Slot, lock_period, commit_period: 0, expires_at: None, taker: None, nft: None, auction: None,
+        })
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn make_with(&mut self, args: anchor_escrow::instruction::Make) -> TransactionResult {
+        self.seed = args.seed;
+
+        let make_ix = self.make_ix(&self.maker.pubkey(), args);
+        send(&mut self.program, make_ix, &self.maker)
+    }
+
+    // Leaves `seed` on the last escrow made by the env's own maker
+    #[allow(clippy::result_large_err)]
+    pub fn make_as(&mut self, maker: &Keypair, args: anchor_escrow::instruction::Make) -> TransactionResult {
+        let make_ix = self.make_ix(&maker.pubkey(), args);
+        send(&mut self.program, make_ix, maker)
+    }
+
+    // Full take with no price limit or referrer
+    #[allow(clippy::result_large_err)]
+    pub fn take(&mut self, taker: &Keypair) -> TransactionResult {
+        self.take_with(taker, anchor_escrow::instruction::Take { max_amount_b: u64::MAX, referrer_fee_bps: 0 })
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn take_with(&mut self, taker: &Keypair, args: anchor_escrow::instruction::Take) -> TransactionResult {
+        let (maker, seed) = (self.maker.pubkey(), self.seed);
+        self.take_from(taker, &maker, seed, args)
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn take_from(&mut self, taker: &Keypair, maker: &Pubkey, seed: u64, args: anchor_escrow::instruction::Take) -> TransactionResult {
+        let take_ix = self.take_ix(&taker.pubkey(), maker, seed, args.data());
+        send(&mut self.program, take_ix, taker)
+    }
+
+    // Partial fill with no price limit or referrer
+    #[allow(clippy::result_large_err)]
+    pub fn take_partial(&mut self, taker: &Keypair, amount_b: u64) -> TransactionResult {
+        self.take_partial_with(taker, anchor_escrow::instruction::TakePartial { amount_b, min_amount_a: 0, referrer_fee_bps: 0 })
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn take_partial_with(&mut self, taker: &Keypair, args: anchor_escrow::instruction::TakePartial) -> TransactionResult {
+        let (maker, seed) = (self.maker.pubkey(), self.seed);
+        self.take_partial_from(taker, &maker, seed, args)
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn take_partial_from(&mut self, taker: &Keypair, maker: &Pubkey, seed: u64, args: anchor_escrow::instruction::TakePartial) -> TransactionResult {
+        let take_ix = self.take_ix(&taker.pubkey(), maker, seed, args.data());
+        send(&mut self.program, take_ix, taker)
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn update(&mut self, args: anchor_escrow::instruction::Update) -> TransactionResult {
+        let maker = self.maker.pubkey();
+
+        let update_ix = Instruction {
+            program_id: anchor_escrow::ID,
+            accounts: anchor_escrow::accounts::Update {
+                maker, mint_a: self.mint_a, maker_ata_a: Some(self.ata(&maker, &self.mint_a)),
+                escrow: self.escrow(), vault: self.vault(), associated_token_program: spl_associated_token_account::ID,
+                token_program: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+            }.to_account_metas(None),
+            data: args.data(),
+        };
+
+        send(&mut self.program, update_ix, &self.maker)
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn refund(&mut self) -> TransactionResult {
+        let refund_ix = self.refund_ix(&self.maker.pubkey(), self.seed);
+        send(&mut self.program, refund_ix, &self.maker)
+    }
+
+    #[allow(clippy::result_large_err)]
+    pub fn refund_as(&mut self, maker: &Keypair, seed: u64) -> TransactionResult {
+        let refund_ix = self.refund_ix(&maker.pubkey(), seed);
+        send(&mut self.program, refund_ix, maker)
+    }
+
+    // Also expires the blockhash, so retrying a rejected instruction is not deduplicated
+    pub fn warp(&mut self, slots: u64) {
+        let slot = self.slot();
+        self.program.warp_to_slot(slot + slots);
+        self.program.expire_blockhash();
+    }
+
+    pub fn slot(&self) -> u64 {
+        self.program.get_sysvar::<anchor_lang::solana_program::clock::Clock>().slot
+    }
+
+    pub fn escrow(&self) -> Pubkey {
+        self.escrow_of(&self.maker.pubkey(), self.seed)
+    }
+
+    pub fn escrow_of(&self, maker: &Pubkey, seed: u64) -> Pubkey {
+        Pubkey::find_program_address(&[b"escrow", maker.as_ref(), &seed.to_le_bytes()], &anchor_escrow::ID).0
+    }
+
+    pub fn vault(&self) -> Pubkey {
+        self.vault_of(&self.maker.pubkey(), self.seed)
+    }
+
+    pub fn vault_of(&self, maker: &Pubkey, seed: u64) -> Pubkey {
+        self.ata(&self.escrow_of(maker, seed), &self.mint_a)
+    }
+
+    pub fn escrow_state(&self) -> Escrow {
+        self.escrow_state_of(&self.maker.pubkey(), self.seed).expect("Escrow should exist")
+    }
+
+    // None once the escrow is closed
+    pub fn escrow_state_of(&self, maker: &Pubkey, seed: u64) -> Option<Escrow> {
+        let escrow = self.escrow_of(maker, seed);
+
+        if !self.exists(&escrow) {
+            return None;
+        }
+
+        Some(Escrow::try_deserialize(&mut self.program.get_account(&escrow).unwrap().data.as_ref()).unwrap())
+    }
+
+    // Closed accounts may linger in LiteSVM with zero lamports
+    pub fn exists(&self, account: &Pubkey) -> bool {
+        self.program.get_account(account).is_some_and(|account| account.lamports > 0)
+    }
+
+    // Mint A held by `owner`'s associated token account, zero if it does not exist
+    pub fn balance_a(&self, owner: &Pubkey) -> u64 {
+        self.balance(&self.ata(owner, &self.mint_a))
+    }
+
+    // Mint B held by `owner`'s associated token account, zero if it does not exist
+    pub fn balance_b(&self, owner: &Pubkey) -> u64 {
+        self.balance(&self.ata(owner, &self.mint_b))
+    }
+
+    pub fn vault_balance(&self) -> u64 {
+        self.balance(&self.vault())
+    }
+
+    pub fn lamports(&self, account: &Pubkey) -> u64 {
+        self.program.get_account(account).map_or(0, |account| account.lamports)
+    }
+
+    // Zero for token accounts that were never created or have been closed
+    pub fn balance(&self, account: &Pubkey) -> u64 {
+        if !self.exists(account) {
+            return 0;
+        }
+
+        get_token_amount(&self.program, account)
+    }
+
+    pub fn supply(&self, mint: &Pubkey) -> u64 {
+        let mint_account = self.program.get_account(mint).expect("Mint should exist");
+        spl_token::state::Mint::unpack(&mint_account.data).unwrap().supply
+    }
+
+    pub fn ata(&self, owner: &Pubkey, mint: &Pubkey) -> Pubkey {
+        associated_token::get_associated_token_address(owner, mint)
+    }
+
+    fn make_ix(&self, maker: &Pubkey, args: anchor_escrow::instruction::Make) -> Instruction {
+        let (mint_a, mint_b) = (self.mint_a, self.mint_b);
+
+        Instruction {
+            program_id: anchor_escrow::ID,
+            accounts: anchor_escrow::accounts::Make {
+                maker: *maker, mint_a, mint_b,
+                maker_ata_a: Some(self.ata(maker, &mint_a)),
+                escrow: self.escrow_of(maker, args.seed), vault: self.vault_of(maker, args.seed),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program_a: TOKEN_PROGRAM_ID, token_program_b: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+                config: config_address(),
+                mint_a_entry: mint_entry_address(&mint_a), mint_b_entry: mint_entry_address(&mint_b),
+            }.to_account_metas(None),
+            data: args.data(),
+        }
+    }
+
+    // The env's own maker is also the config treasury
+    fn take_ix(&self, taker: &Pubkey, maker: &Pubkey, seed: u64, data: Vec<u8>) -> Instruction {
+        let (mint_a, mint_b) = (self.mint_a, self.mint_b);
+
+        Instruction {
+            program_id: anchor_escrow::ID,
+            accounts: anchor_escrow::accounts::Take {
+                taker: *taker, maker: *maker, mint_a, mint_b,
+                taker_ata_a: self.ata(taker, &mint_a), taker_ata_b: Some(self.ata(taker, &mint_b)), maker_ata_b: Some(self.ata(maker, &mint_b)),
+                escrow: self.escrow_of(maker, seed), vault: self.vault_of(maker, seed),
+                associated_token_program: spl_associated_token_account::ID,
+                token_program_a: TOKEN_PROGRAM_ID, token_program_b: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+                config: config_address(), treasury: self.maker.pubkey(), treasury_ata_b: None, referrer_ata_b: None,
+            }.to_account_metas(None),
+            data,
+        }
+    }
+
+    fn refund_ix(&self, maker: &Pubkey, seed: u64) -> Instruction {
+        Instruction {
+            program_id: anchor_escrow::ID,
+            accounts: anchor_escrow::accounts::Refund {
+                maker: *maker, mint_a: self.mint_a, maker_ata_a: Some(self.ata(maker, &self.mint_a)),
+                escrow: self.escrow_of(maker, seed), vault: self.vault_of(maker, seed), token_program: TOKEN_PROGRAM_ID, system_program: SYSTEM_PROGRAM_ID,
+            }.to_account_metas(None),
+            data: anchor_escrow::instruction::Refund {}.data(),
+        }
+    }
+}
@@ -0,0 +1,120 @@
+// Account snapshots for LiteSVM, checked in under `fixtures/accounts/<address>.json` in the
+// `solana account <address> --output json` format, so tests never touch the network.
+//
+// Set ESCROW_CAPTURE_FIXTURES=1 to (re)capture every snapshot a test loads from
+// ESCROW_FIXTURES_RPC_URL (devnet by default) instead of reading the checked-in file.
+
+use {
+    base64::{engine::general_purpose::STANDARD as BASE64, Engine},
+    litesvm::LiteSVM,
+    serde::{Deserialize, Serialize},
+    solana_account::Account,
+    solana_pubkey::Pubkey,
+    std::{fs, path::PathBuf, str::FromStr},
+};
+
+const CAPTURE_ENV: &str = "ESCROW_CAPTURE_FIXTURES";
+const RPC_URL_ENV: &str = "ESCROW_FIXTURES_RPC_URL";
+const DEFAULT_RPC_URL: &str = "https://api.devnet.solana.com";
+
+#[derive(Serialize, Deserialize)]
+struct AccountFixture {
+    pubkey: String,
+    account: UiAccount,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct UiAccount {
+    lamports: u64,
+    data: (String, String), // Encoded data and its encoding, always base64 here
+    owner: String,
+    executable: bool,
+    rent_epoch: u64,
+    space: u64,
+}
+
+fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures/accounts")
+}
+
+fn fixture_path(address: &Pubkey) -> PathBuf {
+    fixtures_dir().join(format!("{address}.json"))
+}
+
+fn capture_enabled() -> bool {
+    std::env::var(CAPTURE_ENV).is_ok_and(|value| value == "1")
+}
+
+// Set a single snapshotted account, capturing it first when capture mode is on
+pub fn load_fixture(program: &mut LiteSVM, address: &Pubkey) -> Account {
+    if capture_enabled() {
+        capture_fixture(address);
+    }
+
+    let path = fixture_path(address);
+    let json = fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("Missing fixture {}, capture it with {CAPTURE_ENV}=1: {err}", path.display()));
+
+    let fixture: AccountFixture = serde_json::from_str(&json)
+        .unwrap_or_else(|err| panic!("Invalid fixture {}: {err}", path.display()));
+
+    assert_eq!(fixture.pubkey, address.to_string(), "Fixture {} is for another address", path.display());
+    assert_eq!(fixture.account.data.1, "base64", "Fixture {} must be base64 encoded", path.display());
+
+    let account = Account {
+        lamports: fixture.account.lamports,
+        data: BASE64.decode(&fixture.account.data.0).expect("Fixture data is not valid base64"),
+        owner: Pubkey::from_str(&fixture.account.owner).expect("Fixture owner is not a valid address"),
+        executable: fixture.account.executable,
+        rent_epoch: fixture.account.rent_epoch,
+    };
+
+    program.set_account(*address, account.clone()).expect("Failed to set fixture account");
+
+    account
+}
+
+// Set every checked-in snapshot, named by its address
+pub fn load_all_fixtures(program: &mut LiteSVM) {
+    let entries = fs::read_dir(fixtures_dir()).expect("Failed to read fixtures directory");
+
+    for entry in entries {
+        let path = entry.expect("Failed to read fixtures directory entry").path();
+
+        if path.extension().is_none_or(|extension| extension != "json") {
+            continue;
+        }
+
+        let address = path.file_stem().and_then(|stem| stem.to_str()).and_then(|stem| Pubkey::from_str(stem).ok())
+            .unwrap_or_else(|| panic!("Fixture {} is not named after an address", path.display()));
+
+        load_fixture(program, &address);
+    }
+}
+
+fn capture_fixture(address: &Pubkey) {
+    use {solana_address::Address, solana_rpc_client::rpc_client::RpcClient};
+
+    let rpc_url = std::env::var(RPC_URL_ENV).unwrap_or_else(|_| DEFAULT_RPC_URL.to_string());
+    let rpc_client = RpcClient::new(rpc_url.clone());
+
+    let account = rpc_client
+        .get_account(&Address::from_str(&address.to_string()).unwrap())
+        .unwrap_or_else(|err| panic!("Failed to capture {address} from {rpc_url}: {err}"));
+
+    let fixture = AccountFixture {
+        pubkey: address.to_string(),
+        account: UiAccount {
+            lamports: account.lamports,
+            space: account.data.len() as u64,
+            data: (BASE64.encode(&account.data), "base64".to_string()),
+            owner: account.owner.to_string(),
+            executable: account.executable,
+            rent_epoch: account.rent_epoch,
+        },
+    };
+
+    fs::create_dir_all(fixtures_dir()).expect("Failed to create fixtures directory");
+    fs::write(fixture_path(address), serde_json::to_string_pretty(&fixture).unwrap() + "\n").expect("Failed to write fixture");
+}
@@ -0,0 +1,134 @@
+// LiteSVM harness shared by the program and client tests: the escrow program deployed from
+// `target/deploy/anchor_escrow.so` with its config initialized, the checked-in account
+// fixtures, and the `EscrowTestEnv` scenario builder.
+
+use {
+    anchor_lang::{
+        solana_program::bpf_loader_upgradeable::{self, UpgradeableLoaderState},
+        InstructionData,
+        ToAccountMetas,
+    },
+    litesvm::{types::TransactionResult, LiteSVM},
+    solana_account::Account,
+    solana_instruction::Instruction,
+    solana_keypair::Keypair,
+    solana_message::Message,
+    solana_native_token::LAMPORTS_PER_SOL,
+    solana_pubkey::Pubkey,
+    solana_sdk_ids::system_program::ID as SYSTEM_PROGRAM_ID,
+    solana_signer::Signer,
+    solana_transaction::Transaction,
+    spl_token_2022::extension::StateWithExtensions,
+    std::path::PathBuf,
+};
+
+mod env;
+pub mod fixtures;
+
+pub use env::*;
+
+use fixtures::load_all_fixtures;
+
+pub const PROGRAM_ID: Pubkey = anchor_escrow::ID;
+
+pub fn setup() -> (LiteSVM, Keypair) {
+    let (mut program, payer) = deploy();
+
+    // Deploy-time config: payer is admin and treasury, no protocol fee, referrers capped at 1%
+    let initialize_config_ix = initialize_config_ix(&payer.pubkey(), 0, 100, payer.pubkey());
+
+    program
+        .send_transaction(Transaction::new(&[&payer], Message::new(&[initialize_config_ix], Some(&payer.pubkey())), program.latest_blockhash()))
+        .expect("Failed to initialize config");
+
+    // Return the LiteSVM instance and payer keypair
+    (program, payer)
+}
+
+// Freshly deployed program with the payer as upgrade authority and no config yet
+pub fn deploy() -> (LiteSVM, Keypair) {
+    // Initialize LiteSVM and payer
+    let mut program = LiteSVM::new();
+    let payer = Keypair::new();
+
+    // Airdrop SOL to the payer keypair (more for tests with multiple escrows)
+    program
+        .airdrop(&payer.pubkey(), 100 * LAMPORTS_PER_SOL)
+        .expect("Failed to airdrop SOL to payer");
+
+    // Load program SO file
+    let so_path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("../../target/deploy/anchor_escrow.so");
+
+    let program_data = std::fs::read(so_path).expect("Failed to read program SO file");
+
+    deploy_upgradeable(&mut program, &program_data, &payer.pubkey());
+
+    // Cluster accounts the tests rely on, e.g. the wSOL mint LiteSVM does not ship
+    load_all_fixtures(&mut program);
+
+    (program, payer)
+}
+
+// `add_program` uses the non-upgradeable loader, but initialize_config checks the upgrade
+// authority recorded in the program data account, so lay both accounts out by hand
+fn deploy_upgradeable(program: &mut LiteSVM, elf: &[u8], upgrade_authority: &Pubkey) {
+    let mut program_data = bincode::serialize(&UpgradeableLoaderState::ProgramData {
+        slot: 0,
+        upgrade_authority_address: Some(*upgrade_authority),
+    }).unwrap();
+    program_data.resize(UpgradeableLoaderState::size_of_programdata_metadata(), 0);
+    program_data.extend_from_slice(elf);
+
+    program.set_account(program_data_address(), Account {
+        lamports: program.minimum_balance_for_rent_exemption(program_data.len()),
+        data: program_data,
+        owner: bpf_loader_upgradeable::ID,
+        executable: false,
+        rent_epoch: 0,
+    }).expect("Failed to set program data account");
+
+    let program_account = bincode::serialize(&UpgradeableLoaderState::Program { programdata_address: program_data_address() }).unwrap();
+
+    program.set_account(PROGRAM_ID, Account {
+        lamports: program.minimum_balance_for_rent_exemption(program_account.len()),
+        data: program_account,
+        owner: bpf_loader_upgradeable::ID,
+        executable: true,
+        rent_epoch: 0,
+    }).expect("Failed to deploy program");
+}
+
+fn program_data_address() -> Pubkey {
+    Pubkey::find_program_address(&[PROGRAM_ID.as_ref()], &bpf_loader_upgradeable::ID).0
+}
+
+pub fn initialize_config_ix(admin: &Pubkey, fee_bps: u16, max_referrer_fee_bps: u16, treasury: Pubkey) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: anchor_escrow::accounts::InitializeConfig {
+            admin: *admin, program: PROGRAM_ID, program_data: program_data_address(), config: config_address(), system_program: SYSTEM_PROGRAM_ID,
+        }.to_account_metas(None),
+        data: anchor_escrow::instruction::InitializeConfig { fee_bps, max_referrer_fee_bps, treasury }.data(),
+    }
+}
+
+pub fn config_address() -> Pubkey {
+    Pubkey::find_program_address(&[b"config"], &PROGRAM_ID).0
+}
+
+pub fn mint_entry_address(mint: &Pubkey) -> Pubkey {
+    Pubkey::find_program_address(&[b"mint", mint.as_ref()], &PROGRAM_ID).0
+}
+
+pub fn get_token_amount(program: &LiteSVM, account: &Pubkey) -> u64 {
+    // Works for both SPL Token and Token-2022 accounts (with or without extensions)
+    let token_account = program.get_account(account).unwrap();
+    StateWithExtensions::<spl_token_2022::state::Account>::unpack(&token_account.data).unwrap().base.amount
+}
+
+#[allow(clippy::result_large_err)]
+pub fn send(program: &mut LiteSVM, ix: Instruction, signer: &Keypair) -> TransactionResult {
+    let transaction = Transaction::new(&[signer], Message::new(&[ix], Some(&signer.pubkey())), program.latest_blockhash());
+    program.send_transaction(transaction)
+}
@@ -0,0 +1,100 @@
+#![allow(unexpected_cfgs)]
+#![allow(deprecated)]
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{Mint, TokenAccount};
+use spl_discriminator::SplDiscriminate;
+use spl_tlv_account_resolution::{account::ExtraAccountMeta, seeds::Seed, state::ExtraAccountMetaList};
+use spl_transfer_hook_interface::instruction::{ExecuteInstruction, InitializeExtraAccountMetaListInstruction};
+
+declare_id!("A3G38NPgedXvvEHqbu56cmE7Hq9RWyXNDKimcdiNXd6q");
+
+// Counts every transfer of a hooked mint so tests can prove the hook ran
+#[program]
+pub mod transfer_hook {
+    use super::*;
+
+    #[instruction(discriminator = InitializeExtraAccountMetaListInstruction::SPL_DISCRIMINATOR_SLICE)]
+    pub fn initialize_extra_account_meta_list(ctx: Context<InitializeExtraAccountMetaList>) -> Result<()> {
+        ExtraAccountMetaList::init::<ExecuteInstruction>(
+            &mut ctx.accounts.extra_account_meta_list.try_borrow_mut_data()?,
+            &extra_account_metas()?,
+        )?;
+
+        ctx.accounts.counter.set_inner(Counter { transfers: 0 });
+
+        Ok(())
+    }
+
+    #[instruction(discriminator = ExecuteInstruction::SPL_DISCRIMINATOR_SLICE)]
+    pub fn transfer_hook(ctx: Context<TransferHook>, _amount: u64) -> Result<()> {
+        ctx.accounts.counter.transfers += 1;
+
+        Ok(())
+    }
+}
+
+// The counter PDA is the only extra account, seeded by the mint (index 1 of Execute)
+fn extra_account_metas() -> Result<Vec<ExtraAccountMeta>> {
+    Ok(vec![ExtraAccountMeta::new_with_seeds(
+        &[
+            Seed::Literal { bytes: b"counter".to_vec() },
+            Seed::AccountKey { index: 1 },
+        ],
+        false,
+        true,
+    )?])
+}
+
+#[derive(Accounts)]
+pub struct InitializeExtraAccountMetaList<'info> {
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    /// CHECK: ExtraAccountMetaList account, written by `ExtraAccountMetaList::init`
+    #[account(
+        init,
+        payer = payer,
+        space = ExtraAccountMetaList::size_of(extra_account_metas()?.len())?,
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump,
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(
+        init,
+        payer = payer,
+        space = 8 + Counter::INIT_SPACE,
+        seeds = [b"counter", mint.key().as_ref()],
+        bump,
+    )]
+    pub counter: Account<'info, Counter>,
+    pub system_program: Program<'info, System>,
+}
+
+// Account order is fixed by the transfer hook interface's Execute instruction
+#[derive(Accounts)]
+pub struct TransferHook<'info> {
+    pub source_token: InterfaceAccount<'info, TokenAccount>,
+    pub mint: InterfaceAccount<'info, Mint>,
+    pub destination_token: InterfaceAccount<'info, TokenAccount>,
+    /// CHECK: source token account owner, may be a PDA
+    pub owner: UncheckedAccount<'info>,
+    /// CHECK: ExtraAccountMetaList account, validated by its seeds
+    #[account(
+        seeds = [b"extra-account-metas", mint.key().as_ref()],
+        bump,
+    )]
+    pub extra_account_meta_list: UncheckedAccount<'info>,
+    #[account(
+        mut,
+        seeds = [b"counter", mint.key().as_ref()],
+        bump,
+    )]
+    pub counter: Account<'info, Counter>,
+}
+
+#[account]
+#[derive(InitSpace, Debug)]
+pub struct Counter {
+    pub transfers: u64,
+}